@@ -2,20 +2,80 @@
 //!
 //! Appbiotic Code CLI is a tool to manage your coding projects.
 
-use clap::Parser;
+use appbiotic_code_error::{
+    exit::{Exit, Output},
+    Error,
+};
+use clap::{Parser, ValueEnum};
 use tracing::{event, Level};
 
 /// A tool to manage your coding projects.
 #[derive(Parser)]
+#[command(version)]
 struct Cli {
+    /// The format errors are reported in
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Human)]
+    output: OutputFormat,
+
     #[cfg(feature = "appbiotic-examples")]
     #[command(subcommand)]
     command: appbiotic_examples::commands::CliCmd,
 }
 
-pub fn main() -> anyhow::Result<()> {
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// Message, field violations, localized message and help links
+    Human,
+    /// google.rpc.Status JSON
+    Json,
+}
+
+impl From<OutputFormat> for Output {
+    fn from(value: OutputFormat) -> Self {
+        match value {
+            OutputFormat::Human => Output::Human,
+            OutputFormat::Json => Output::Json,
+        }
+    }
+}
+
+/// Reports an error on standard error in the `--output` format and exits with
+/// a sysexits.h code of the error, see [`appbiotic_code_error::exit`]. Invalid
+/// arguments exit with `EX_USAGE`, while `--help` and `--version` succeed.
+pub fn main() -> Exit {
     appbiotic_code_runtime::init_telemetry(true);
     event!(Level::TRACE, "appbiotic_code_runtime telemetry initialized");
-    let cli = Cli::parse();
-    cli.command.execute()
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        // clap reports `--help` and `--version` as errors for standard output.
+        Err(err) if !err.use_stderr() => {
+            return Exit::new(err.print().map_err(|err| {
+                Error::internal("Failed to print to standard output").with_error(err)
+            }));
+        }
+        Err(err) => {
+            let error = Error::invalid_argument("Invalid command line arguments")
+                .with_error(err.to_string().trim_end().to_owned());
+            return Exit::new(Err(error)).with_output(output_of_invalid_args());
+        }
+    };
+    Exit::new(cli.command.execute()).with_output(cli.output.into())
+}
+
+/// Returns the last valid `--output` format of arguments that failed to
+/// parse, defaulting to the human form.
+fn output_of_invalid_args() -> Output {
+    let mut args = std::env::args().skip(1).take_while(|arg| arg != "--");
+    let mut output = OutputFormat::Human;
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--output") {
+            Some("") => args.next(),
+            Some(value) => value.strip_prefix('=').map(str::to_owned),
+            None => None,
+        };
+        if let Some(format) = value.and_then(|value| OutputFormat::from_str(&value, false).ok()) {
+            output = format;
+        }
+    }
+    output.into()
 }
@@ -0,0 +1,72 @@
+//! Benchmarks of the error paths of request handlers.
+//!
+//! Run with `cargo bench -p appbiotic-code-error --features full`.
+
+use std::hint::black_box;
+
+use appbiotic_code_error::{Code, Error, ErrorDetails, Field, FieldViolation, Property};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn invalid_greeting() -> Error {
+    Error::invalid_argument("Invalid greeting").with_details(ErrorDetails::bad_request(
+        FieldViolation {
+            field: Field::new(Property::Member {
+                name: "name".to_string(),
+            }),
+            description: Some("Too long".to_string()),
+        },
+    ))
+}
+
+fn construction(c: &mut Criterion) {
+    c.bench_function("construct/message", |b| {
+        b.iter(|| Error::not_found(black_box("Greeting not found")))
+    });
+    c.bench_function("construct/static_message", |b| {
+        b.iter(|| Error::with_code(Code::NotFound, black_box("Greeting not found")))
+    });
+    c.bench_function("construct/formatted_message", |b| {
+        b.iter(|| Error::not_found(format!("Greeting {} not found", black_box(42))))
+    });
+    c.bench_function("construct/bad_request", |b| b.iter(invalid_greeting));
+    c.bench_function("construct/ok_result", |b| {
+        b.iter(|| -> appbiotic_code_error::Result<u64> { Ok(black_box(42)) })
+    });
+}
+
+fn with_error(c: &mut Criterion) {
+    c.bench_function("with_error/io_error", |b| {
+        b.iter(|| {
+            Error::unavailable("Greeter unavailable").with_error(std::io::Error::new(
+                std::io::ErrorKind::ConnectionReset,
+                "reset by peer",
+            ))
+        })
+    });
+}
+
+fn http(c: &mut Criterion) {
+    c.bench_function("http/status", |b| {
+        let error = invalid_greeting();
+        b.iter(|| black_box(&error).http_status())
+    });
+    c.bench_function("http/problem_response", |b| {
+        b.iter(|| invalid_greeting().into_problem_response())
+    });
+    c.bench_function("http/google_json_response", |b| {
+        b.iter(|| invalid_greeting().into_google_json_response())
+    });
+}
+
+fn grpc(c: &mut Criterion) {
+    c.bench_function("grpc/into_tonic_status", |b| {
+        b.iter(|| invalid_greeting().into_tonic_status())
+    });
+    c.bench_function("grpc/from_tonic_status", |b| {
+        let status = invalid_greeting().into_tonic_status();
+        b.iter(|| Error::try_from(black_box(status.clone())))
+    });
+}
+
+criterion_group!(benches, construction, with_error, http, grpc);
+criterion_main!(benches);
@@ -0,0 +1,188 @@
+//! Capture of backtraces when an [`Error`](crate::Error) is created.
+//!
+//! By default a backtrace is captured as [`Backtrace::capture`] does, i.e.,
+//! when the `RUST_LIB_BACKTRACE` or, if unset, the `RUST_BACKTRACE`
+//! environment variable is set to a value other than `0`. Services can
+//! override this at runtime with [`set_capture`].
+//!
+//! A captured backtrace is rendered by the alternate `Display` of the error,
+//! e.g., in logs. It is only sent to clients, as the `stack_entries` of a
+//! `ErrorDetails::DebugInfo`, when asked for with
+//! [`Error::with_stack_entries`](crate::Error::with_stack_entries).
+
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+/// When to capture a backtrace for a new error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Capture {
+    /// Capture depending on the `RUST_LIB_BACKTRACE` and `RUST_BACKTRACE`
+    /// environment variables.
+    #[default]
+    Environment,
+    /// Always capture.
+    Enabled,
+    /// Never capture.
+    Disabled,
+}
+
+static CAPTURE: AtomicU8 = AtomicU8::new(0);
+
+/// Sets when to capture a backtrace for errors created afterwards.
+pub fn set_capture(capture: Capture) {
+    let value = match capture {
+        Capture::Environment => 0,
+        Capture::Enabled => 1,
+        Capture::Disabled => 2,
+    };
+    CAPTURE.store(value, Ordering::Relaxed);
+}
+
+/// Returns when a backtrace is captured for a new error.
+pub fn capture() -> Capture {
+    match CAPTURE.load(Ordering::Relaxed) {
+        1 => Capture::Enabled,
+        2 => Capture::Disabled,
+        _ => Capture::Environment,
+    }
+}
+
+/// Captures a backtrace of the caller if enabled.
+pub(crate) fn capture_backtrace() -> Option<Arc<Backtrace>> {
+    let backtrace = match capture() {
+        Capture::Environment => Backtrace::capture(),
+        Capture::Enabled => Backtrace::force_capture(),
+        Capture::Disabled => return None,
+    };
+    (backtrace.status() == BacktraceStatus::Captured).then(|| Arc::new(backtrace))
+}
+
+/// Resolves the frames of `backtrace` into stack entries of the form
+/// `function at file:line:column`, leaving out the frames of the capture
+/// itself and of this crate at the top of the stack, e.g., of
+/// [`Error::internal`](crate::Error::internal) or a `From` conversion.
+pub(crate) fn stack_entries(backtrace: &Backtrace) -> Vec<String> {
+    parse_frames(&backtrace.to_string())
+}
+
+fn parse_frames(rendered: &str) -> Vec<String> {
+    let mut entries: Vec<String> = Vec::new();
+    for line in rendered.lines() {
+        let line = line.trim();
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(entry) = entries.last_mut() {
+                entry.push_str(" at ");
+                entry.push_str(location);
+            }
+        } else if let Some((index, function)) = line.split_once(": ") {
+            if index.bytes().all(|b| b.is_ascii_digit()) {
+                entries.push(function.to_owned());
+            }
+        }
+    }
+    // Standard library frames, e.g., of `map_err`, may come between the
+    // frames of the crate, so they are left out up to the last of them.
+    let internal = entries
+        .iter()
+        .take_while(|entry| is_crate_frame(entry) || is_std_frame(entry))
+        .enumerate()
+        .filter(|(_, entry)| is_crate_frame(entry) || entry.starts_with("std::backtrace"))
+        .last()
+        .map_or(0, |(index, _)| index + 1);
+    entries.split_off(internal)
+}
+
+/// Whether the frame is of a function of this crate, other than its tests.
+fn is_crate_frame(entry: &str) -> bool {
+    entry.contains("appbiotic_code_error::") && !entry.contains("::tests::")
+}
+
+fn is_std_frame(entry: &str) -> bool {
+    [
+        "std::", "core::", "alloc::", "<std::", "<core::", "<alloc::",
+    ]
+    .iter()
+    .any(|prefix| entry.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rendered_frames() {
+        let rendered = "   0: std::backtrace_rs::backtrace::libunwind::trace
+             at /rustc/library/std/src/../../backtrace/src/backtrace/libunwind.rs:116:5
+   1: std::backtrace::Backtrace::create
+             at /rustc/library/std/src/backtrace.rs:331:13
+   2: appbiotic_code_error::backtrace::capture_backtrace
+             at ./src/backtrace.rs:54:34
+   3: greeter::greet
+             at ./src/main.rs:12:9
+   4: main
+";
+        assert_eq!(
+            parse_frames(rendered),
+            ["greeter::greet at ./src/main.rs:12:9", "main"]
+        );
+    }
+
+    #[test]
+    fn parse_frames_of_crate_helpers() {
+        let rendered = "   0: std::backtrace::Backtrace::force_capture
+   1: appbiotic_code_error::backtrace::capture_backtrace
+   2: appbiotic_code_error::ErrorStatus::captured
+   3: <core::result::Result<T,E> as appbiotic_code_error::ext::ResultExt<T>>::or_code_with::{{closure}}
+   4: core::result::Result<T,E>::map_err
+   5: <core::result::Result<T,E> as appbiotic_code_error::ext::ResultExt<T>>::or_internal
+   6: greeter::greet
+   7: core::ops::function::FnOnce::call_once
+";
+        assert_eq!(
+            parse_frames(rendered),
+            ["greeter::greet", "core::ops::function::FnOnce::call_once"]
+        );
+    }
+
+    fn first_entry(error: &crate::Error) -> String {
+        let entries = error.inner().stack_entries().expect("stack entries");
+        entries.first().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn stack_entries_start_at_the_caller() {
+        use crate::{ext::ResultExt, Error};
+
+        set_capture(Capture::Enabled);
+        let error = Error::internal("Greeting lost");
+        let entry = first_entry(&error);
+        assert!(
+            entry.starts_with("appbiotic_code_error::backtrace::tests::stack_entries_start_at"),
+            "{entry}"
+        );
+
+        let error = "x"
+            .parse::<u32>()
+            .or_internal("Invalid greeting count")
+            .unwrap_err();
+        let entry = first_entry(&error);
+        assert!(
+            entry.starts_with("appbiotic_code_error::backtrace::tests::stack_entries_start_at"),
+            "{entry}"
+        );
+    }
+
+    #[test]
+    fn stack_entries_of_forced_capture() {
+        let entries = stack_entries(&Backtrace::force_capture());
+        assert!(!entries.is_empty());
+        assert!(entries
+            .iter()
+            .any(|entry| entry.contains("stack_entries_of_forced_capture")));
+    }
+}
@@ -0,0 +1,501 @@
+//! Statuses of batch operations that fail for some items and not others.
+//!
+//! A [`BatchStatus`] records the outcome of each item, keyed by its index or
+//! [`Field`], and converts into a single [`Error`] with:
+//!
+//! - the code of the failed items if they all have the same code, or
+//!   [`Code::Unknown`] if their codes differ;
+//! - a message counting the failed items, e.g., `2 of 3 items failed`;
+//! - a `ErrorDetails::ErrorInfo` for each failed item, in [`DOMAIN`], with the
+//!   item's code name as the reason and its key and message as metadata,
+//!   followed by the item's own details, encoded with its causes as a single
+//!   error is, see [`ErrorStatus::encoded_details`]. The fields of an item's
+//!   `ErrorDetails::BadRequest` are prefixed with the item key, e.g.,
+//!   `greetings["jo"].name`, or `items[1].name` for an index.
+//!
+//! [`BatchStatus::from_error`] rebuilds the failed items of a decoded error,
+//! assigning each of the details to the item whose `ErrorInfo` precedes it.
+//! An item whose reason is not a code name is [`Code::Unknown`], and one whose
+//! key cannot be parsed is keyed by an [unparsed](Field::unparsed) field, so
+//! that none of the details are lost.
+//!
+//! ```
+//! use appbiotic_code_error::{batch::CollectAll, Code, Error};
+//!
+//! let names = ["Kris", "", "Jo"];
+//! let greetings: Result<Vec<String>, _> = names
+//!     .iter()
+//!     .map(|name| match name.is_empty() {
+//!         true => Err(Error::invalid_argument("Name is empty")),
+//!         false => Ok(format!("Hello, {name}!")),
+//!     })
+//!     .collect_all();
+//! let error = greetings.unwrap_err().into_result().unwrap_err();
+//! assert_eq!(error.code(), Code::InvalidArgument);
+//! assert_eq!(
+//!     error.to_string(),
+//!     "INVALID_ARGUMENT: 1 of 3 items failed (error info: INVALID_ARGUMENT in \
+//!      batch.appbiotic.com: item=1, message=Name is empty)"
+//! );
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::{Code, Error, ErrorDetails, ErrorStatus, Field, FieldViolation, Property};
+
+/// The domain of the `ErrorDetails::ErrorInfo` of failed items.
+pub const DOMAIN: &str = "batch.appbiotic.com";
+
+/// The `ErrorInfo` metadata key of the item key.
+pub const ITEM_KEY: &str = "item";
+
+/// The `ErrorInfo` metadata key of the item message.
+pub const MESSAGE_KEY: &str = "message";
+
+/// The name of the array that prefixes the fields of an item keyed by its
+/// index, e.g., `items[1].name`.
+pub const ITEMS_NAME: &str = "items";
+
+/// The key of an item of a batch: its index in the request or the field of
+/// the request it was given in.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ItemKey {
+    Index(usize),
+    Field(Field),
+}
+
+impl From<usize> for ItemKey {
+    fn from(value: usize) -> Self {
+        ItemKey::Index(value)
+    }
+}
+
+impl From<Field> for ItemKey {
+    fn from(value: Field) -> Self {
+        ItemKey::Field(value)
+    }
+}
+
+impl From<Property> for ItemKey {
+    fn from(value: Property) -> Self {
+        ItemKey::Field(Field::new(value))
+    }
+}
+
+impl fmt::Display for ItemKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItemKey::Index(index) => write!(f, "{index}"),
+            ItemKey::Field(field) => write!(f, "{field}"),
+        }
+    }
+}
+
+impl ItemKey {
+    /// Returns the properties that prefix the fields of the item's details.
+    fn prefix(&self) -> Vec<Property> {
+        match self {
+            ItemKey::Index(index) => vec![Property::ArrayMember {
+                name: ITEMS_NAME.to_owned(),
+                index: *index,
+            }],
+            ItemKey::Field(field) => field.properties().cloned().collect(),
+        }
+    }
+
+    /// Prefixes `field` of the item's details with the key.
+    fn prefixed(&self, field: &Field) -> Field {
+        self.prefix()
+            .into_iter()
+            .rev()
+            .fold(field.clone(), Field::with_context)
+    }
+
+    /// Strips the key from `field` if it is prefixed with it.
+    fn unprefixed(&self, field: &Field) -> Field {
+        let prefix = self.prefix();
+        let len = field.properties().len();
+        if len <= prefix.len() || !field.properties().zip(&prefix).all(|(a, b)| a == b) {
+            return field.clone();
+        }
+        let mut properties = field.properties().rev().take(len - prefix.len()).cloned();
+        match properties.next() {
+            Some(leaf) => properties.fold(Field::new(leaf), Field::with_context),
+            None => field.clone(),
+        }
+    }
+
+    /// Maps the fields of `details` with `f` if it is a `BadRequest`.
+    fn map_fields<F: Fn(&Field) -> Field>(details: &ErrorDetails, f: F) -> ErrorDetails {
+        match details {
+            ErrorDetails::BadRequest { field_violations } => ErrorDetails::BadRequest {
+                field_violations: field_violations
+                    .iter()
+                    .map(|violation| FieldViolation {
+                        field: f(&violation.field),
+                        description: violation.description.clone(),
+                    })
+                    .collect(),
+            },
+            details => details.clone(),
+        }
+    }
+}
+
+impl FromStr for ItemKey {
+    type Err = Error;
+
+    /// Parses an index if `s` is all digits and a [`Field`] otherwise.
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s.parse() {
+            Ok(index) => Ok(ItemKey::Index(index)),
+            Err(_) => Ok(ItemKey::Field(Field::from_str(s)?)),
+        }
+    }
+}
+
+/// The outcome of each item of a batch operation.
+#[derive(Clone, Debug, Default)]
+pub struct BatchStatus {
+    items: Vec<(ItemKey, Option<Error>)>,
+}
+
+impl BatchStatus {
+    pub fn new() -> Self {
+        BatchStatus::default()
+    }
+
+    /// Records an item that succeeded.
+    pub fn with_ok<K: Into<ItemKey>>(mut self, key: K) -> Self {
+        self.items.push((key.into(), None));
+        self
+    }
+
+    /// Records an item that failed with `error`.
+    pub fn with_error<K: Into<ItemKey>>(mut self, key: K, error: Error) -> Self {
+        self.items.push((key.into(), Some(error)));
+        self
+    }
+
+    /// Records the outcome of an item, returning its value if it succeeded.
+    pub fn push<K: Into<ItemKey>, T>(&mut self, key: K, result: crate::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.items.push((key.into(), None));
+                Some(value)
+            }
+            Err(error) => {
+                self.items.push((key.into(), Some(error)));
+                None
+            }
+        }
+    }
+
+    /// Returns the number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the keys and errors of the failed items.
+    pub fn errors(&self) -> impl Iterator<Item = (&ItemKey, &Error)> {
+        self.items
+            .iter()
+            .filter_map(|(key, error)| error.as_ref().map(|error| (key, error)))
+    }
+
+    /// Whether every item succeeded.
+    pub fn is_ok(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Returns the overall code, `None` if every item succeeded, see the
+    /// [module](self) documentation.
+    pub fn code(&self) -> Option<Code> {
+        let mut codes = self.errors().map(|(_, error)| error.code());
+        let code = codes.next()?;
+        Some(if codes.all(|other| other == code) {
+            code
+        } else {
+            Code::Unknown
+        })
+    }
+
+    /// Returns the error of the batch, `None` if every item succeeded.
+    pub fn to_error(&self) -> Option<Error> {
+        let code = self.code()?;
+        let failed = self.errors().count();
+        let status = self.errors().fold(
+            ErrorStatus::captured()
+                .with_message(format!("{failed} of {} items failed", self.items.len())),
+            |status, (key, error)| {
+                let error_info = ErrorDetails::error_info(error.code().as_str(), DOMAIN)
+                    .with_metadata(ITEM_KEY, key.to_string());
+                let error_info = match &error.inner().message {
+                    Some(message) => error_info.with_metadata(MESSAGE_KEY, message),
+                    None => error_info,
+                };
+                error.inner().encoded_details().iter().fold(
+                    status.with_details(error_info),
+                    |status, details| {
+                        status
+                            .with_details(ItemKey::map_fields(details, |field| key.prefixed(field)))
+                    },
+                )
+            },
+        );
+        Some(Error::new(code, status))
+    }
+
+    /// Converts the status into `Ok` if every item succeeded and into the
+    /// error of the batch otherwise.
+    pub fn into_result(self) -> crate::Result<()> {
+        match self.to_error() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Recovers the failed items from the details of a decoded batch error.
+    /// Items that succeeded are not part of the error, so only failed items
+    /// are returned. The details following the `ErrorInfo` of an item are its
+    /// own, with the item key stripped from the fields of a `BadRequest`.
+    pub fn from_error(error: &Error) -> Option<BatchStatus> {
+        let mut items: Vec<(ItemKey, Option<Error>)> = Vec::new();
+        let mut current: Option<(ItemKey, Code, ErrorStatus)> = None;
+        for details in error.inner().details.iter().flatten() {
+            match details {
+                ErrorDetails::ErrorInfo {
+                    reason,
+                    domain,
+                    metadata,
+                } if domain == DOMAIN => {
+                    items.extend(
+                        current
+                            .take()
+                            .map(|(key, code, status)| (key, Some(Error::new(code, status)))),
+                    );
+                    let code = Code::from_str(reason).unwrap_or(Code::Unknown);
+                    let key = metadata.get(ITEM_KEY).map_or("", String::as_str);
+                    let key = ItemKey::from_str(key)
+                        .unwrap_or_else(|_| ItemKey::Field(Field::unparsed(key)));
+                    let status = match metadata.get(MESSAGE_KEY) {
+                        Some(message) => ErrorStatus::default().with_message(message.clone()),
+                        None => ErrorStatus::default(),
+                    };
+                    current = Some((key, code, status));
+                }
+                details => {
+                    if let Some((key, code, status)) = current.take() {
+                        let details = ItemKey::map_fields(details, |field| key.unprefixed(field));
+                        current = Some((key, code, status.with_details(details)));
+                    }
+                }
+            }
+        }
+        items.extend(current.map(|(key, code, status)| (key, Some(Error::new(code, status)))));
+        (!items.is_empty()).then_some(BatchStatus { items })
+    }
+}
+
+/// Collects the values of an iterator of results like `collect::<Result<_>>()`
+/// but without stopping at the first error.
+pub trait CollectAll<T>: Iterator<Item = crate::Result<T>> + Sized {
+    /// Collects every value if every item succeeded, or returns the status of
+    /// every item keyed by its index.
+    fn collect_all<C: FromIterator<T>>(self) -> Result<C, BatchStatus> {
+        self.enumerate().collect_all_keyed()
+    }
+}
+
+impl<T, I: Iterator<Item = crate::Result<T>>> CollectAll<T> for I {}
+
+/// Like [`CollectAll`] for an iterator of keyed results.
+pub trait CollectAllKeyed<K: Into<ItemKey>, T>:
+    Iterator<Item = (K, crate::Result<T>)> + Sized
+{
+    fn collect_all_keyed<C: FromIterator<T>>(self) -> Result<C, BatchStatus> {
+        let mut status = BatchStatus::new();
+        let mut values = Vec::new();
+        for (key, result) in self {
+            values.extend(status.push(key, result));
+        }
+        if status.is_ok() {
+            Ok(values.into_iter().collect())
+        } else {
+            Err(status)
+        }
+    }
+}
+
+impl<K: Into<ItemKey>, T, I: Iterator<Item = (K, crate::Result<T>)>> CollectAllKeyed<K, T> for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str) -> ItemKey {
+        Property::MapMember {
+            name: "greetings".to_string(),
+            key: name.to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn batch_code() {
+        assert_eq!(BatchStatus::new().with_ok(0).code(), None);
+        let status = BatchStatus::new()
+            .with_ok(0)
+            .with_error(1, Error::not_found("Greeting not found"))
+            .with_error(2, Error::not_found("Greeting not found"));
+        assert_eq!(status.code(), Some(Code::NotFound));
+        let status = status.with_error(3, Error::unavailable("Try later"));
+        assert_eq!(status.code(), Some(Code::Unknown));
+        assert_eq!(status.len(), 4);
+        assert_eq!(status.errors().count(), 3);
+    }
+
+    #[test]
+    fn batch_error_round_trip() {
+        let error = BatchStatus::new()
+            .with_ok(item("kris"))
+            .with_error(item("jo"), Error::already_exists("Greeting exists"))
+            .into_result()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "ALREADY_EXISTS: 1 of 2 items failed (error info: ALREADY_EXISTS in \
+             batch.appbiotic.com: item=greetings[\"jo\"], message=Greeting exists)"
+        );
+
+        let decoded = crate::proto::decode_status(&crate::proto::encode_status(&error))
+            .expect("decoded status");
+        let status = BatchStatus::from_error(&decoded).expect("batch status");
+        let errors: Vec<(&ItemKey, &Error)> = status.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, &item("jo"));
+        assert_eq!(errors[0].1.code(), Code::AlreadyExists);
+        assert_eq!(
+            errors[0].1.inner().message.as_deref(),
+            Some("Greeting exists")
+        );
+        assert!(BatchStatus::from_error(&Error::internal("Oops")).is_none());
+    }
+
+    #[test]
+    fn batch_error_keeps_item_details() {
+        let name = Field::new(Property::Member {
+            name: "name".to_string(),
+        });
+        let invalid = Error::invalid_argument("Invalid greeting")
+            .with_details(ErrorDetails::bad_request(FieldViolation {
+                field: name.clone(),
+                description: Some("Too long".to_string()),
+            }))
+            .with_details(ErrorDetails::error_info(
+                "NAME_TOO_LONG",
+                "greeter.appbiotic.com",
+            ))
+            .with_error("Name has 300 characters");
+        let error = BatchStatus::new()
+            .with_error(item("jo"), invalid)
+            .with_error(
+                1,
+                Error::invalid_argument("Invalid recipient").with_details(
+                    ErrorDetails::bad_request(FieldViolation {
+                        field: name.clone(),
+                        description: None,
+                    }),
+                ),
+            )
+            .to_error()
+            .expect("batch error");
+        let fields: Vec<String> = error
+            .inner()
+            .details
+            .iter()
+            .flatten()
+            .filter_map(|details| match details {
+                ErrorDetails::BadRequest { field_violations } => Some(field_violations),
+                _ => None,
+            })
+            .flatten()
+            .map(|violation| violation.field.to_string())
+            .collect();
+        assert_eq!(fields, [r#"greetings["jo"].name"#, "items[1].name"]);
+
+        let decoded = crate::proto::decode_status(&crate::proto::encode_status(&error))
+            .expect("decoded status");
+        let status = BatchStatus::from_error(&decoded).expect("batch status");
+        let errors: Vec<(&ItemKey, &Error)> = status.errors().collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].0, &item("jo"));
+        match errors[0].1.inner().details.as_deref() {
+            Some(
+                [ErrorDetails::BadRequest { field_violations }, ErrorDetails::ErrorInfo { reason, .. }, ErrorDetails::DebugInfo {
+                    detail: Some(detail),
+                    ..
+                }],
+            ) => {
+                assert_eq!(field_violations[0].field, name);
+                assert_eq!(reason, "NAME_TOO_LONG");
+                assert_eq!(detail, "Name has 300 characters");
+            }
+            other => panic!("unexpected details: {other:?}"),
+        }
+        assert_eq!(errors[1].0, &ItemKey::Index(1));
+        assert!(matches!(
+            errors[1].1.inner().details.as_deref(),
+            Some([ErrorDetails::BadRequest { field_violations }])
+                if field_violations[0].field == name
+        ));
+    }
+
+    #[test]
+    fn batch_error_keeps_details_of_unparsed_items() {
+        let error = Error::unknown("1 of 1 items failed")
+            .with_details(
+                ErrorDetails::error_info("NOT_A_CODE", DOMAIN).with_metadata(ITEM_KEY, "[jo"),
+            )
+            .with_details(ErrorDetails::debug_info("Greeter crashed"));
+        let status = BatchStatus::from_error(&error).expect("batch status");
+        let errors: Vec<(&ItemKey, &Error)> = status.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, &ItemKey::Field(Field::unparsed("[jo")));
+        assert_eq!(errors[0].1.code(), Code::Unknown);
+        assert!(matches!(
+            errors[0].1.inner().details.as_deref(),
+            Some([ErrorDetails::DebugInfo { detail: Some(detail), .. }])
+                if detail == "Greeter crashed"
+        ));
+    }
+
+    #[test]
+    fn collect_all() {
+        let values: Vec<u32> = ["1", "2"]
+            .iter()
+            .map(|s| s.parse().map_err(|_| Error::invalid_argument("NaN")))
+            .collect_all()
+            .expect("all parsed");
+        assert_eq!(values, [1, 2]);
+
+        let status = ["1", "x", "y"]
+            .iter()
+            .map(|s| s.parse::<u32>().map_err(|_| Error::invalid_argument("NaN")))
+            .collect_all::<Vec<_>>()
+            .unwrap_err();
+        let keys: Vec<&ItemKey> = status.errors().map(|(key, _)| key).collect();
+        assert_eq!(keys, [&ItemKey::Index(1), &ItemKey::Index(2)]);
+
+        let status = [("kris", Ok(1)), ("jo", Err(Error::not_found("Missing")))]
+            .into_iter()
+            .map(|(name, result)| (item(name), result))
+            .collect_all_keyed::<Vec<u32>>()
+            .unwrap_err();
+        assert_eq!(status.code(), Some(Code::NotFound));
+    }
+}
@@ -0,0 +1,473 @@
+//! Message catalogs for attaching a `ErrorDetails::LocalizedMessage` in the
+//! language preferred by the client.
+//!
+//! A [`Catalog`] holds a [`Bundle`] of messages per locale. Messages are
+//! looked up by id and may have `{name}` parameters:
+//!
+//! ```text
+//! # fr.properties
+//! greeting.name_too_long = Le nom doit comporter au plus {max} caractères.
+//! ```
+//!
+//! A [`Resolver`] picks the locales from an `Accept-Language` header, or the
+//! `accept-language` key of gRPC metadata, and falls back from a regional
+//! locale to its language and then to the catalog's default locale, e.g.,
+//! `fr-CA` → `fr` → `en`.
+//!
+//! ```
+//! use appbiotic_code_error::{
+//!     catalog::{Bundle, Catalog, Message},
+//!     Error,
+//! };
+//!
+//! let catalog = Catalog::new("en")
+//!     .with_bundle(Bundle::new("en").with_message("too_long", "Use at most {max} characters."))
+//!     .with_bundle(Bundle::new("fr").with_message("too_long", "Au plus {max} caractères."));
+//! let resolver = catalog.resolver("fr-CA, fr;q=0.9, en;q=0.5");
+//! let error = resolver.localize(
+//!     Error::invalid_argument("Name too long"),
+//!     &Message::new("too_long").with_arg("max", 20),
+//! );
+//! assert_eq!(
+//!     error.to_string(),
+//!     "INVALID_ARGUMENT: Name too long (localized message: [fr] Au plus 20 caractères.)"
+//! );
+//! ```
+
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+use crate::{Error, ErrorDetails, Result};
+
+/// The file extension of bundles loaded by [`Catalog::load_dir`].
+pub const BUNDLE_EXTENSION: &str = "properties";
+
+/// A reference to a catalog message with the values of its parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub id: String,
+    pub args: Vec<(String, String)>,
+}
+
+impl Message {
+    pub fn new<I: AsRef<str>>(id: I) -> Self {
+        Message {
+            id: id.as_ref().to_owned(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Sets the value of the parameter `name`.
+    pub fn with_arg<N: AsRef<str>, V: fmt::Display>(mut self, name: N, value: V) -> Self {
+        self.args
+            .push((name.as_ref().to_owned(), value.to_string()));
+        self
+    }
+}
+
+/// The messages of a single locale.
+#[derive(Clone, Debug, Default)]
+pub struct Bundle {
+    locale: String,
+    messages: HashMap<String, String>,
+}
+
+impl Bundle {
+    pub fn new<L: AsRef<str>>(locale: L) -> Self {
+        Bundle {
+            locale: locale.as_ref().to_owned(),
+            messages: HashMap::new(),
+        }
+    }
+
+    /// Parses a bundle of `id = message` lines. Blank lines and lines
+    /// starting with `#` are ignored.
+    pub fn parse<L: AsRef<str>>(locale: L, source: &str) -> Result<Self> {
+        let mut bundle = Bundle::new(locale);
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (id, message) = line
+                .split_once('=')
+                .map(|(id, message)| (id.trim(), message.trim()))
+                .filter(|(id, _)| !id.is_empty())
+                .ok_or_else(|| {
+                    Error::invalid_argument(format!(
+                        "Invalid message in bundle {} at line {}",
+                        bundle.locale,
+                        number + 1
+                    ))
+                })?;
+            bundle.messages.insert(id.to_owned(), message.to_owned());
+        }
+        Ok(bundle)
+    }
+
+    pub fn with_message<I: AsRef<str>, M: AsRef<str>>(mut self, id: I, message: M) -> Self {
+        self.messages
+            .insert(id.as_ref().to_owned(), message.as_ref().to_owned());
+        self
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Formats `message`, replacing each `{name}` with the value of the
+    /// parameter; `{{` and `}}` stand for literal braces. Parameters without a
+    /// value are left as is.
+    pub fn format(&self, message: &Message) -> Option<String> {
+        let template = self.messages.get(&message.id)?;
+        let mut formatted = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find(['{', '}']) {
+            formatted.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                formatted.push_str(&rest[..1]);
+                rest = &rest[2..];
+                continue;
+            }
+            let arg = rest.strip_prefix('{').and_then(|after| {
+                let (name, after) = after.split_once('}')?;
+                let (_, value) = message.args.iter().find(|(arg, _)| arg == name)?;
+                Some((value, after))
+            });
+            match arg {
+                Some((value, after)) => {
+                    formatted.push_str(value);
+                    rest = after;
+                }
+                None => {
+                    formatted.push_str(&rest[..1]);
+                    rest = &rest[1..];
+                }
+            }
+        }
+        formatted.push_str(rest);
+        Some(formatted)
+    }
+}
+
+/// The bundles of all supported locales.
+#[derive(Clone, Debug)]
+pub struct Catalog {
+    default_locale: String,
+    /// The bundles by lowercase locale.
+    bundles: HashMap<String, Bundle>,
+}
+
+impl Catalog {
+    /// Creates an empty catalog falling back to `default_locale`.
+    pub fn new<L: AsRef<str>>(default_locale: L) -> Self {
+        Catalog {
+            default_locale: default_locale.as_ref().to_owned(),
+            bundles: HashMap::new(),
+        }
+    }
+
+    /// Loads a bundle from each `<locale>.properties` file in `dir`.
+    pub fn load_dir<L: AsRef<str>, P: AsRef<Path>>(default_locale: L, dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let read_error = |err| {
+            Error::internal(format!("Failed to read message catalog {}", dir.display()))
+                .with_error(err)
+        };
+        let mut catalog = Catalog::new(default_locale);
+        for entry in fs::read_dir(dir).map_err(read_error)? {
+            let path = entry.map_err(read_error)?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(BUNDLE_EXTENSION) {
+                continue;
+            }
+            let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let source = fs::read_to_string(&path).map_err(read_error)?;
+            catalog = catalog.with_bundle(Bundle::parse(locale, &source)?);
+        }
+        Ok(catalog)
+    }
+
+    /// Adds `bundle`, merging it into any bundle of the same locale.
+    pub fn with_bundle(mut self, bundle: Bundle) -> Self {
+        let key = bundle.locale.to_ascii_lowercase();
+        match self.bundles.get_mut(&key) {
+            Some(existing) => existing.messages.extend(bundle.messages),
+            None => {
+                self.bundles.insert(key, bundle);
+            }
+        }
+        self
+    }
+
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    pub fn bundle(&self, locale: &str) -> Option<&Bundle> {
+        self.bundles.get(&locale.to_ascii_lowercase())
+    }
+
+    /// Returns the locales to try for the requested `locales`, in order: each
+    /// locale followed by its less specific ones, e.g., `fr-CA` then `fr`, and
+    /// lastly the default locale.
+    pub fn fallback_chain<S: AsRef<str>>(&self, locales: &[S]) -> Vec<String> {
+        let mut chain: Vec<String> = Vec::new();
+        let requested = locales
+            .iter()
+            .map(AsRef::as_ref)
+            .chain([self.default_locale.as_str()]);
+        for locale in requested {
+            let mut locale = locale;
+            loop {
+                if !locale.is_empty()
+                    && !chain
+                        .iter()
+                        .any(|chained| chained.eq_ignore_ascii_case(locale))
+                {
+                    chain.push(locale.to_owned());
+                }
+                match locale.rsplit_once('-') {
+                    Some((parent, _)) => locale = parent,
+                    None => break,
+                }
+            }
+        }
+        chain
+    }
+
+    /// Formats `message` in the first locale of the fallback chain of
+    /// `locales` that has it, returning the locale and the text.
+    pub fn format<S: AsRef<str>>(
+        &self,
+        locales: &[S],
+        message: &Message,
+    ) -> Option<(&str, String)> {
+        self.fallback_chain(locales).iter().find_map(|locale| {
+            let bundle = self.bundle(locale)?;
+            Some((bundle.locale(), bundle.format(message)?))
+        })
+    }
+
+    /// Creates a resolver for the locales of an `Accept-Language` header.
+    pub fn resolver(&self, accept_language: &str) -> Resolver<'_> {
+        Resolver {
+            catalog: self,
+            locales: parse_accept_language(accept_language),
+        }
+    }
+
+    /// Creates a resolver for the `Accept-Language` header of a request.
+    #[cfg(feature = "with-http")]
+    pub fn resolver_from_headers(&self, headers: &http::HeaderMap) -> Resolver<'_> {
+        let accept_language = headers
+            .get_all(http::header::ACCEPT_LANGUAGE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.resolver(&accept_language)
+    }
+
+    /// Creates a resolver for the `accept-language` key of gRPC metadata.
+    #[cfg(feature = "with-tonic")]
+    pub fn resolver_from_metadata(&self, metadata: &tonic::metadata::MetadataMap) -> Resolver<'_> {
+        let accept_language = metadata
+            .get_all("accept-language")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.resolver(&accept_language)
+    }
+}
+
+/// Formats catalog messages in the locales requested by a client.
+#[derive(Clone, Debug)]
+pub struct Resolver<'a> {
+    catalog: &'a Catalog,
+    locales: Vec<String>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Creates a resolver for `locales` in order of preference.
+    pub fn new<S: AsRef<str>>(catalog: &'a Catalog, locales: &[S]) -> Self {
+        Resolver {
+            catalog,
+            locales: locales
+                .iter()
+                .map(|locale| locale.as_ref().to_owned())
+                .collect(),
+        }
+    }
+
+    /// Returns the requested locales in order of preference.
+    pub fn locales(&self) -> &[String] {
+        &self.locales
+    }
+
+    /// Formats `message`, returning its locale and text.
+    pub fn format(&self, message: &Message) -> Option<(&'a str, String)> {
+        self.catalog.format(&self.locales, message)
+    }
+
+    /// Appends a `ErrorDetails::LocalizedMessage` for `message` to `error`,
+    /// unless no locale has the message.
+    pub fn localize(&self, error: Error, message: &Message) -> Error {
+        match self.format(message) {
+            Some((locale, text)) => {
+                error.with_details(ErrorDetails::localized_message(locale, text))
+            }
+            None => error,
+        }
+    }
+}
+
+/// Parses the language ranges of an `Accept-Language` header ordered by
+/// their quality values, see
+/// https://www.rfc-editor.org/rfc/rfc9110#name-accept-language.
+///
+/// The wildcard `*` and ranges with a quality of zero are left out.
+pub fn parse_accept_language(value: &str) -> Vec<String> {
+    let mut ranges: Vec<(u16, &str)> = value
+        .split(',')
+        .filter_map(|range| {
+            let mut params = range.split(';');
+            let language = params.next()?.trim();
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(parse_quality)
+                .unwrap_or(1000);
+            (!language.is_empty() && language != "*" && quality > 0).then_some((quality, language))
+        })
+        .collect();
+    ranges.sort_by(|(a, _), (b, _)| b.cmp(a));
+    ranges
+        .into_iter()
+        .map(|(_, language)| language.to_owned())
+        .collect()
+}
+
+/// Parses a quality value into thousandths.
+fn parse_quality(value: &str) -> Option<u16> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if fraction.len() > 3 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let fraction = format!("{fraction:0<3}").parse::<u16>().ok()?;
+    match whole {
+        "0" => Some(fraction),
+        "1" if fraction == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        let en = "# Greeter messages\n\
+                  too_long = Use at most {max} characters.\n\
+                  closed = The greeter is closed.\n";
+        let fr = "too_long = Au plus {max} caractères.\n";
+        let fr_ca = "closed = Le salueur est fermé, {{ {day} }}.\n";
+        Catalog::new("en")
+            .with_bundle(Bundle::parse("en", en).expect("en bundle"))
+            .with_bundle(Bundle::parse("fr", fr).expect("fr bundle"))
+            .with_bundle(Bundle::parse("fr-CA", fr_ca).expect("fr-CA bundle"))
+    }
+
+    #[test]
+    fn accept_language() {
+        assert_eq!(
+            parse_accept_language("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5"),
+            ["fr-CH", "fr", "en", "de"]
+        );
+        assert_eq!(
+            parse_accept_language("en;q=0.5, es;q=0, fr-CA;q=0.75"),
+            ["fr-CA", "en"]
+        );
+        assert!(parse_accept_language("").is_empty());
+    }
+
+    #[test]
+    fn fallback_chain() {
+        assert_eq!(
+            catalog().fallback_chain(&["fr-CA", "de-AT"]),
+            ["fr-CA", "fr", "de-AT", "de", "en"]
+        );
+    }
+
+    #[test]
+    fn format_with_fallback() {
+        let catalog = catalog();
+        let resolver = catalog.resolver("fr-CA");
+        assert_eq!(
+            resolver.format(&Message::new("closed").with_arg("day", "lundi")),
+            Some(("fr-CA", "Le salueur est fermé, { lundi }.".to_string()))
+        );
+        assert_eq!(
+            resolver.format(&Message::new("too_long").with_arg("max", 20)),
+            Some(("fr", "Au plus 20 caractères.".to_string()))
+        );
+        assert_eq!(
+            catalog
+                .resolver("de")
+                .format(&Message::new("too_long").with_arg("max", 20)),
+            Some(("en", "Use at most 20 characters.".to_string()))
+        );
+        assert_eq!(
+            catalog.resolver("en").format(&Message::new("too_long")),
+            Some(("en", "Use at most {max} characters.".to_string()))
+        );
+        assert_eq!(resolver.format(&Message::new("unknown")), None);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_lines() {
+        assert!(Bundle::parse("en", "too_long Use at most 20 characters.").is_err());
+        assert!(Bundle::parse("en", " = Missing id").is_err());
+    }
+
+    #[test]
+    fn load_dir() {
+        let dir = std::env::temp_dir().join(format!("appbiotic-catalog-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("catalog dir");
+        fs::write(dir.join("en.properties"), "closed = Closed\n").expect("en bundle");
+        fs::write(dir.join("fr-CA.properties"), "closed = Fermé\n").expect("fr-CA bundle");
+        fs::write(dir.join("README.md"), "Not a bundle").expect("readme");
+        let catalog = Catalog::load_dir("en", &dir);
+        fs::remove_dir_all(&dir).expect("removed catalog dir");
+
+        let catalog = catalog.expect("loaded catalog");
+        let error = catalog
+            .resolver("fr-ca")
+            .localize(Error::unavailable("Closed"), &Message::new("closed"));
+        assert!(matches!(
+            error.inner().details.as_deref(),
+            Some([ErrorDetails::LocalizedMessage { locale, message }])
+                if locale == "fr-CA" && message == "Fermé"
+        ));
+    }
+
+    #[cfg(feature = "with-http")]
+    #[test]
+    fn resolver_from_headers() {
+        let mut headers = http::HeaderMap::new();
+        headers.append(
+            http::header::ACCEPT_LANGUAGE,
+            http::HeaderValue::from_static("de;q=0.5"),
+        );
+        headers.append(
+            http::header::ACCEPT_LANGUAGE,
+            http::HeaderValue::from_static("fr-CA"),
+        );
+        let catalog = catalog();
+        assert_eq!(
+            catalog.resolver_from_headers(&headers).locales(),
+            ["fr-CA", "de"]
+        );
+    }
+}
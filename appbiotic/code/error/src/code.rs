@@ -0,0 +1,434 @@
+//! The canonical error codes of
+//! https://github.com/googleapis/googleapis/blob/f36c65081b19e0758ef5696feca27c7dcee5475e/google/rpc/code.proto.
+
+use std::{fmt, str::FromStr};
+
+use strum_macros::IntoStaticStr;
+
+use crate::{Error, Result};
+
+pub const OK: i32 = 0;
+pub const CANCELLED: i32 = 1;
+pub const UNKNOWN: i32 = 2;
+pub const INVALID_ARGUMENT: i32 = 3;
+pub const DEADLINE_EXCEEDED: i32 = 4;
+pub const NOT_FOUND: i32 = 5;
+pub const ALREADY_EXISTS: i32 = 6;
+pub const PERMISSION_DENIED: i32 = 7;
+pub const UNAUTHENTICATED: i32 = 16;
+pub const RESOURCE_EXHAUSTED: i32 = 8;
+pub const FAILED_PRECONDITION: i32 = 9;
+pub const ABORTED: i32 = 10;
+pub const OUT_OF_RANGE: i32 = 11;
+pub const UNIMPLEMENTED: i32 = 12;
+pub const INTERNAL: i32 = 13;
+pub const UNAVAILABLE: i32 = 14;
+pub const DATA_LOSS: i32 = 15;
+
+/// The code of an [`Error`], i.e., any canonical code except `OK`.
+///
+/// The discriminant of each code is its gRPC code value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, IntoStaticStr)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+#[repr(i32)]
+pub enum Code {
+    /// The operation was cancelled, typically by the caller.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  499 | Client Closed Request                               |
+    /// | gRPC    |    1 | Cancelled                                           |
+    Cancelled = CANCELLED,
+
+    /// Unknown error. For example, this error may be returned when a [`ErrorStatus`](crate::ErrorStatus)
+    /// value received from another address space belongs to an error space
+    /// that is not known in this address space. Also errors raised by APIs
+    /// that do not return enough error information may be converted to this
+    /// error.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  500 | Internal Server Error                               |
+    /// | gRPC    |    2 | Unknown                                             |
+    Unknown = UNKNOWN,
+
+    /// The client specified an invalid argument.  Note that this differs
+    /// from [`Code::FailedPrecondition`].  [`Code::InvalidArgument`] indicates arguments
+    /// that are problematic regardless of the state of the system
+    /// (e.g., a malformed file name).
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  400 | Bad Request                                         |
+    /// | gRPC    |    3 | Invalid argument                                    |
+    InvalidArgument = INVALID_ARGUMENT,
+
+    /// The deadline expired before the operation could complete. For operations
+    /// that change the state of the system, this error may be returned
+    /// even if the operation has completed successfully.  For example, a
+    /// successful response from a server could have been delayed long
+    /// enough for the deadline to expire.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  504 | Gateway Timeout                                     |
+    /// | gRPC    |    4 | Deadline exceeded                                   |
+    DeadlineExceeded = DEADLINE_EXCEEDED,
+
+    /// Some requested entity (e.g., file or directory) was not found.
+    ///
+    /// Note to server developers: if a request is denied for an entire class
+    /// of users, such as gradual feature rollout or undocumented allowlist,
+    /// [`Code::NotFound`] may be used. If a request is denied for some users
+    /// within a class of users, such as user-based access control,
+    /// [`Code::PermissionDenied`] must be used.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  404 | Not Found                                           |
+    /// | gRPC    |    5 | Not found                                           |
+    NotFound = NOT_FOUND,
+
+    /// The entity that a client attempted to create (e.g., file or directory)
+    /// already exists.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  409 | Conflict                                            |
+    /// | gRPC    |    6 | Already exists                                      |
+    AlreadyExists = ALREADY_EXISTS,
+
+    /// The caller does not have permission to execute the specified
+    /// operation. [`Code::PermissionDenied`] must not be used for rejections
+    /// caused by exhausting some resource (use [`Code::ResourceExhausted`]
+    /// instead for those errors). [`Code::PermissionDenied`] must not be
+    /// used if the caller can not be identified (use [`Code::Unauthenticated`]
+    /// instead for those errors). This error code does not imply the
+    /// request is valid or the requested entity exists or satisfies
+    /// other pre-conditions.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  403 | Forbidden                                           |
+    /// | gRPC    |    7 | Permission denied                                   |
+    PermissionDenied = PERMISSION_DENIED,
+
+    /// The request does not have valid authentication credentials for the
+    /// operation.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  401 | Unauthorized                                        |
+    /// | gRPC    |   16 | Permission denied                                   |
+    Unauthenticated = UNAUTHENTICATED,
+
+    /// Some resource has been exhausted, perhaps a per-user quota, or
+    /// perhaps the entire file system is out of space.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  429 | Too Many Requests                                   |
+    /// | gRPC    |    8 | Permission denied                                   |
+    ResourceExhausted = RESOURCE_EXHAUSTED,
+
+    /// The operation was rejected because the system is not in a state
+    /// required for the operation's execution.  For example, the directory
+    /// to be deleted is non-empty, an rmdir operation is applied to
+    /// a non-directory, etc.
+    ///
+    /// Service implementors can use the following guidelines to decide
+    /// between [`Code::FailedPrecondition`], [`Code::Aborted`], and
+    /// [`Code::Unavailable`]:
+    ///
+    ///  - Use [`Code::Unavailable`] if the client can retry just the failing
+    ///    call.
+    ///  - Use [`Code::Aborted`] if the client should retry at a higher level.
+    ///    For example, when a client-specified test-and-set fails, indicating
+    ///    the client should restart a read-modify-write sequence.
+    ///  - Use [`Code::FailedPrecondition`] if the client should not retry
+    ///    until the system state has been explicitly fixed. For example, if an
+    ///    "rmdir" fails because the directory is non-empty,
+    ///    [`Code::FailedPrecondition`] should be returned since the client
+    ///    should not retry unless the files are deleted from the directory.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  400 | Bad Request                                         |
+    /// | gRPC    |    9 | Failed precondition                                 |
+    FailedPrecondition = FAILED_PRECONDITION,
+
+    /// The operation was aborted, typically due to a concurrency issue such as
+    /// a sequencer check failure or transaction abort.
+    ///
+    /// See the guidelines above for deciding between
+    /// [`Code::FailedPrecondition`], [`Code::Aborted`], and
+    /// [`Code::Unavailable`].
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  409 | Conflict                                            |
+    /// | gRPC    |   10 | Aborted                                             |
+    Aborted = ABORTED,
+
+    /// The operation was attempted past the valid range.  E.g., seeking or
+    /// reading past end-of-file.
+    ///
+    /// Unlike [`Code::InvalidArgument`], this error indicates a problem that
+    /// may be fixed if the system state changes. For example, a 32-bit file
+    /// system will generate [`Code::InvalidArgument`] if asked to read at an
+    /// offset that is not in the range [0,2^32-1], but it will generate
+    /// [`Code::OutOfRange`] if asked to read from an offset past the current
+    /// file size.
+    ///
+    /// There is a fair bit of overlap between [`Code::FailedPrecondition`] and
+    /// [`Code::OutOfRange`].  We recommend using [`Code::OutOfRange`] (the
+    /// more specific error) when it applies so that callers who are iterating
+    /// through a space can easily look for an [`Code::OutOfRange`] error to
+    /// detect when they are done.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  400 | Bad Request                                         |
+    /// | gRPC    |   11 | Out of range                                        |
+    OutOfRange = OUT_OF_RANGE,
+
+    /// The operation is not implemented or is not supported/enabled in this
+    /// service.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  501 | Not implemented                                     |
+    /// | gRPC    |   12 | Unimplemented                                       |
+    Unimplemented = UNIMPLEMENTED,
+
+    /// Internal errors.  This means that some invariants expected by the
+    /// underlying system have been broken.  This error code is reserved for
+    /// serious errors.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  500 | Internal Server Error                               |
+    /// | gRPC    |   13 | Internal                                            |
+    Internal = INTERNAL,
+
+    /// The service is currently unavailable.  This is most likely a transient
+    /// condition, which can be corrected by retrying with
+    /// a backoff. Note that it is not always safe to retry
+    /// non-idempotent operations.
+    ///
+    /// See the guidelines above for deciding between
+    /// [`Code::FailedPrecondition`], [`Code::Aborted`], and
+    /// [`Code::Unavailable`].
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  503 | Service Unavailable                                 |
+    /// | gRPC    |   14 | Unavailable                                         |
+    Unavailable = UNAVAILABLE,
+
+    /// Unrecoverable data loss or corruption.
+    ///
+    /// | Mapping | Code | Description                                         |
+    /// | :------ | ---: | :-------------------------------------------------- |
+    /// | HTTP    |  500 | Internal Server Error                               |
+    /// | gRPC    |   15 | Data loss                                           |
+    DataLoss = DATA_LOSS,
+}
+
+impl Code {
+    /// All codes in the order of their gRPC code values.
+    pub const ALL: [Code; 16] = [
+        Code::Cancelled,
+        Code::Unknown,
+        Code::InvalidArgument,
+        Code::DeadlineExceeded,
+        Code::NotFound,
+        Code::AlreadyExists,
+        Code::PermissionDenied,
+        Code::ResourceExhausted,
+        Code::FailedPrecondition,
+        Code::Aborted,
+        Code::OutOfRange,
+        Code::Unimplemented,
+        Code::Internal,
+        Code::Unavailable,
+        Code::DataLoss,
+        Code::Unauthenticated,
+    ];
+
+    /// Returns the `SCREAMING_SNAKE_CASE` name, e.g., `INVALID_ARGUMENT`.
+    pub fn as_str(&self) -> &'static str {
+        self.into()
+    }
+
+    /// Returns the gRPC code value.
+    pub fn value(&self) -> i32 {
+        *self as i32
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Code {
+    type Err = Error;
+
+    /// Parses the `SCREAMING_SNAKE_CASE` name of a code, e.g.,
+    /// `INVALID_ARGUMENT`.
+    fn from_str(s: &str) -> Result<Self> {
+        Code::ALL
+            .into_iter()
+            .find(|code| code.as_str() == s)
+            .ok_or_else(|| Error::invalid_argument(format!("Unknown status code name: {s}")))
+    }
+}
+
+impl TryFrom<i32> for Code {
+    type Error = Error;
+
+    /// Converts a gRPC code value, failing for [`OK`] or any value outside of
+    /// the known codes.
+    fn try_from(value: i32) -> Result<Self> {
+        match value {
+            OK => Err(Error::invalid_argument("Cannot convert OK status to Error")),
+            _ => Code::ALL
+                .into_iter()
+                .find(|code| code.value() == value)
+                .ok_or_else(|| Error::invalid_argument(format!("Unknown status code: {value}"))),
+        }
+    }
+}
+
+impl From<Code> for i32 {
+    fn from(value: Code) -> Self {
+        value.value()
+    }
+}
+
+#[cfg(feature = "with-http")]
+impl Code {
+    /// Returns the HTTP status code from the mapping table of the code.
+    pub fn http_status(&self) -> http::StatusCode {
+        match self {
+            Code::Cancelled => {
+                http::StatusCode::from_u16(499).unwrap_or(http::StatusCode::IM_A_TEAPOT)
+            }
+            Code::Unknown => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Code::InvalidArgument => http::StatusCode::BAD_REQUEST,
+            Code::DeadlineExceeded => http::StatusCode::GATEWAY_TIMEOUT,
+            Code::NotFound => http::StatusCode::NOT_FOUND,
+            Code::AlreadyExists => http::StatusCode::CONFLICT,
+            Code::PermissionDenied => http::StatusCode::FORBIDDEN,
+            Code::Unauthenticated => http::StatusCode::UNAUTHORIZED,
+            Code::ResourceExhausted => http::StatusCode::TOO_MANY_REQUESTS,
+            Code::FailedPrecondition => http::StatusCode::BAD_REQUEST,
+            Code::Aborted => http::StatusCode::CONFLICT,
+            Code::OutOfRange => http::StatusCode::BAD_REQUEST,
+            Code::Unimplemented => http::StatusCode::NOT_IMPLEMENTED,
+            Code::Internal => http::StatusCode::INTERNAL_SERVER_ERROR,
+            Code::Unavailable => http::StatusCode::SERVICE_UNAVAILABLE,
+            Code::DataLoss => http::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Returns the code for an HTTP error `status` received from another
+    /// service.
+    ///
+    /// The code is chosen by reversing the mapping tables of the codes.
+    /// Where several codes share a status, the one suggesting the least
+    /// specific client action is chosen:
+    ///
+    /// | HTTP              | Code                         |
+    /// | :---------------- | :--------------------------- |
+    /// | 400               | [`Code::InvalidArgument`]    |
+    /// | 401               | [`Code::Unauthenticated`]    |
+    /// | 403               | [`Code::PermissionDenied`]   |
+    /// | 404               | [`Code::NotFound`]           |
+    /// | 408               | [`Code::DeadlineExceeded`]   |
+    /// | 409               | [`Code::Aborted`]            |
+    /// | 416               | [`Code::OutOfRange`]         |
+    /// | 429               | [`Code::ResourceExhausted`]  |
+    /// | 499               | [`Code::Cancelled`]          |
+    /// | 501               | [`Code::Unimplemented`]      |
+    /// | 503               | [`Code::Unavailable`]        |
+    /// | 504               | [`Code::DeadlineExceeded`]   |
+    /// | Other 4xx         | [`Code::FailedPrecondition`] |
+    /// | Other 5xx         | [`Code::Internal`]           |
+    /// | Any other status  | [`Code::Unknown`]            |
+    pub fn from_http_status(status: http::StatusCode) -> Code {
+        match status.as_u16() {
+            400 => Code::InvalidArgument,
+            401 => Code::Unauthenticated,
+            403 => Code::PermissionDenied,
+            404 => Code::NotFound,
+            408 => Code::DeadlineExceeded,
+            409 => Code::Aborted,
+            416 => Code::OutOfRange,
+            429 => Code::ResourceExhausted,
+            499 => Code::Cancelled,
+            501 => Code::Unimplemented,
+            503 => Code::Unavailable,
+            504 => Code::DeadlineExceeded,
+            _ if status.is_client_error() => Code::FailedPrecondition,
+            _ if status.is_server_error() => Code::Internal,
+            _ => Code::Unknown,
+        }
+    }
+}
+
+#[cfg(feature = "with-http")]
+impl From<Code> for http::StatusCode {
+    fn from(value: Code) -> Self {
+        value.http_status()
+    }
+}
+
+#[cfg(feature = "with-tonic")]
+impl From<Code> for tonic::Code {
+    fn from(value: Code) -> Self {
+        tonic::Code::from(value.value())
+    }
+}
+
+#[cfg(feature = "with-tonic")]
+impl TryFrom<tonic::Code> for Code {
+    type Error = Error;
+
+    fn try_from(value: tonic::Code) -> Result<Self> {
+        Code::try_from(value as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_values_and_names() {
+        for (index, code) in Code::ALL.into_iter().enumerate() {
+            assert_eq!(code.value(), index as i32 + 1);
+            assert_eq!(Code::try_from(code.value()).expect("known value"), code);
+            assert_eq!(code.to_string().parse::<Code>().expect("known name"), code);
+        }
+        assert_eq!(Code::Unauthenticated.value(), UNAUTHENTICATED);
+        assert_eq!(Code::InvalidArgument.to_string(), "INVALID_ARGUMENT");
+        assert!(Code::try_from(OK).is_err());
+        assert!(Code::try_from(17).is_err());
+        assert!("OK".parse::<Code>().is_err());
+        assert!("invalid_argument".parse::<Code>().is_err());
+    }
+
+    #[cfg(feature = "with-tonic")]
+    #[test]
+    fn tonic_codes() {
+        for code in Code::ALL {
+            let tonic_code = tonic::Code::from(code);
+            assert_eq!(tonic_code as i32, code.value());
+            assert_eq!(Code::try_from(tonic_code).expect("error code"), code);
+        }
+        assert!(Code::try_from(tonic::Code::Ok).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Canonical conversions of common standard library and ecosystem errors into
+//! an [`Error`], so that `?` can be used on them directly.
+//!
+//! The conversions are opt-in, since they decide the code of errors that
+//! would otherwise need an explicit mapping:
+//!
+//! | Feature           | Errors                                 |
+//! | :---------------- | :------------------------------------- |
+//! | `with-std-errors` | `io::Error`                            |
+//! | `with-std-errors` | `ParseIntError`, `ParseFloatError`     |
+//! | `with-std-errors` | `Utf8Error`, `FromUtf8Error`           |
+//! | `with-serde-json` | `serde_json::Error`                    |
+//!
+//! The original error is kept as the source of the converted error.
+//!
+//! An `io::Error` is mapped by its kind:
+//!
+//! | `io::ErrorKind`                                      | Code                 |
+//! | :--------------------------------------------------- | :------------------- |
+//! | `NotFound`                                           | `NOT_FOUND`          |
+//! | `PermissionDenied`                                   | `PERMISSION_DENIED`  |
+//! | `AlreadyExists`                                      | `ALREADY_EXISTS`     |
+//! | `TimedOut`                                           | `DEADLINE_EXCEEDED`  |
+//! | `InvalidInput`, `InvalidData`                        | `INVALID_ARGUMENT`   |
+//! | `UnexpectedEof`                                      | `OUT_OF_RANGE`       |
+//! | `Unsupported`                                        | `UNIMPLEMENTED`      |
+//! | `OutOfMemory`                                        | `RESOURCE_EXHAUSTED` |
+//! | `ConnectionRefused`, `ConnectionReset`               | `UNAVAILABLE`        |
+//! | `ConnectionAborted`, `NotConnected`, `BrokenPipe`    | `UNAVAILABLE`        |
+//! | `AddrInUse`, `AddrNotAvailable`                      | `UNAVAILABLE`        |
+//! | `WouldBlock`, `Interrupted`                          | `ABORTED`            |
+//! | Any other kind                                       | `INTERNAL`           |
+//!
+//! Parse and UTF-8 errors are [`Code::InvalidArgument`]. A `serde_json::Error`
+//! is [`Code::InvalidArgument`] with a message locating the problem by line and
+//! column, unless it is an I/O error, which is mapped as above. Use
+//! `de::from_str` to report the path of the offending field as an
+//! `ErrorDetails::BadRequest` instead.
+
+use std::io;
+#[cfg(feature = "with-std-errors")]
+use std::{
+    num::{ParseFloatError, ParseIntError},
+    str::Utf8Error,
+    string::FromUtf8Error,
+};
+
+use crate::Code;
+#[cfg(any(feature = "with-std-errors", feature = "with-serde-json"))]
+use crate::{Error, ErrorStatus};
+
+/// Returns the canonical code of an I/O error of `kind`, see the
+/// [module](self) documentation.
+pub fn io_error_code(kind: io::ErrorKind) -> Code {
+    use io::ErrorKind::*;
+    match kind {
+        NotFound => Code::NotFound,
+        PermissionDenied => Code::PermissionDenied,
+        AlreadyExists => Code::AlreadyExists,
+        TimedOut => Code::DeadlineExceeded,
+        InvalidInput | InvalidData => Code::InvalidArgument,
+        UnexpectedEof => Code::OutOfRange,
+        Unsupported => Code::Unimplemented,
+        OutOfMemory => Code::ResourceExhausted,
+        ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected | BrokenPipe
+        | AddrInUse | AddrNotAvailable => Code::Unavailable,
+        WouldBlock | Interrupted => Code::Aborted,
+        _ => Code::Internal,
+    }
+}
+
+#[cfg(feature = "with-std-errors")]
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::new(
+            io_error_code(value.kind()),
+            ErrorStatus::captured()
+                .with_message(format!("I/O error: {}", value.kind()))
+                .with_error(value),
+        )
+    }
+}
+
+#[cfg(feature = "with-std-errors")]
+impl From<ParseIntError> for Error {
+    fn from(value: ParseIntError) -> Self {
+        Error::invalid_argument("Invalid integer").with_error(value)
+    }
+}
+
+#[cfg(feature = "with-std-errors")]
+impl From<ParseFloatError> for Error {
+    fn from(value: ParseFloatError) -> Self {
+        Error::invalid_argument("Invalid number").with_error(value)
+    }
+}
+
+#[cfg(feature = "with-std-errors")]
+impl From<Utf8Error> for Error {
+    fn from(value: Utf8Error) -> Self {
+        Error::invalid_argument("Invalid UTF-8").with_error(value)
+    }
+}
+
+#[cfg(feature = "with-std-errors")]
+impl From<FromUtf8Error> for Error {
+    fn from(value: FromUtf8Error) -> Self {
+        Error::invalid_argument("Invalid UTF-8").with_error(value)
+    }
+}
+
+#[cfg(feature = "with-serde-json")]
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        if value.is_io() {
+            let code = std::error::Error::source(&value)
+                .and_then(|source| source.downcast_ref::<io::Error>())
+                .map_or(Code::Internal, |source| io_error_code(source.kind()));
+            return Error::new(
+                code,
+                ErrorStatus::captured()
+                    .with_message("I/O error while processing JSON")
+                    .with_error(value),
+            );
+        }
+        Error::new(
+            Code::InvalidArgument,
+            ErrorStatus::captured()
+                .with_message(format!("Invalid JSON: {value}"))
+                .with_error(value),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "with-serde-json")]
+    use crate::ErrorDetails;
+
+    #[test]
+    fn io_error_codes() {
+        assert_eq!(
+            io_error_code(io::ErrorKind::UnexpectedEof),
+            Code::OutOfRange
+        );
+        assert_eq!(io_error_code(io::ErrorKind::BrokenPipe), Code::Unavailable);
+        assert_eq!(io_error_code(io::ErrorKind::Interrupted), Code::Aborted);
+        assert_eq!(io_error_code(io::ErrorKind::WouldBlock), Code::Aborted);
+        assert_eq!(io_error_code(io::ErrorKind::Other), Code::Internal);
+    }
+
+    #[cfg(feature = "with-std-errors")]
+    #[test]
+    fn io_errors() {
+        let cases = [
+            (io::ErrorKind::NotFound, Code::NotFound),
+            (io::ErrorKind::PermissionDenied, Code::PermissionDenied),
+            (io::ErrorKind::TimedOut, Code::DeadlineExceeded),
+            (io::ErrorKind::AlreadyExists, Code::AlreadyExists),
+            (io::ErrorKind::ConnectionReset, Code::Unavailable),
+            (io::ErrorKind::Other, Code::Internal),
+        ];
+        for (kind, code) in cases {
+            let error = Error::from(io::Error::new(kind, "greetings.txt"));
+            assert_eq!(error.code(), code, "{kind:?}");
+            assert_eq!(
+                error.find_source::<io::Error>().map(io::Error::kind),
+                Some(kind)
+            );
+        }
+        assert_eq!(
+            Error::from(io::Error::from(io::ErrorKind::NotFound)).to_string(),
+            "NOT_FOUND: I/O error: entity not found"
+        );
+    }
+
+    #[cfg(feature = "with-std-errors")]
+    #[test]
+    fn parse_errors() {
+        fn parse(count: &[u8]) -> crate::Result<u32> {
+            Ok(std::str::from_utf8(count)?.parse()?)
+        }
+
+        assert_eq!(parse(b"3").unwrap(), 3);
+        let error = parse(b"three").unwrap_err();
+        assert_eq!(error.to_string(), "INVALID_ARGUMENT: Invalid integer");
+        assert!(error.find_source::<std::num::ParseIntError>().is_some());
+        let error = parse(&[0xff]).unwrap_err();
+        assert_eq!(error.to_string(), "INVALID_ARGUMENT: Invalid UTF-8");
+    }
+
+    #[cfg(feature = "with-serde-json")]
+    #[test]
+    fn serde_json_errors() {
+        let error =
+            Error::from(serde_json::from_str::<serde_json::Value>("{\n  \"name\": }").unwrap_err());
+        assert_eq!(error.code(), Code::InvalidArgument);
+        assert_eq!(
+            error.to_string(),
+            "INVALID_ARGUMENT: Invalid JSON: expected value at line 2 column 11"
+        );
+        assert!(error
+            .inner()
+            .details
+            .iter()
+            .flatten()
+            .all(|details| !matches!(details, ErrorDetails::BadRequest { .. })));
+        assert!(matches!(
+            error.inner().encoded_details().last(),
+            Some(ErrorDetails::DebugInfo { detail: Some(detail), .. })
+                if detail == "expected value at line 2 column 11"
+        ));
+    }
+}
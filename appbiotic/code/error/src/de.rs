@@ -0,0 +1,1096 @@
+//! Deserialization of requests that reports the offending field of a failure
+//! as a `ErrorDetails::BadRequest`.
+//!
+//! The deserializer is wrapped to track the path as it descends into struct
+//! members, map entries and sequence elements. When deserialization fails, the
+//! path is rendered as a [`Field`] of [`Property::Member`],
+//! [`Property::MapMember`] and [`Property::ArrayMember`], e.g.,
+//! `children[1].nicknames["joe"]`, and returned in a
+//! [`Code::InvalidArgument`] error.
+//!
+//! A missing field is reported at the path of the field itself and an unknown
+//! field at the path of the unknown key.
+
+use std::{cell::RefCell, fmt};
+
+use serde::de::{self, DeserializeSeed, Visitor};
+
+use crate::{Code, Error, ErrorStatus, Field, FieldViolation, Property, Result};
+
+/// Deserializes a `T` from `deserializer`, reporting the path of a failure.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+    D: de::Deserializer<'de>,
+    D::Error: Send + Sync + 'static,
+{
+    let track = Track::default();
+    T::deserialize(Tracked::new(deserializer, &track)).map_err(|err| {
+        let description = err.to_string();
+        track.into_error(description, err)
+    })
+}
+
+/// Deserializes a `T` from a JSON string, reporting the path of a failure.
+///
+/// Syntax errors, which have no path, are reported by line and column.
+///
+/// ```
+/// use appbiotic_code_error::de;
+///
+/// #[derive(Debug, serde::Deserialize)]
+/// struct Child {
+///     #[allow(dead_code)]
+///     age: u32,
+/// }
+///
+/// #[derive(Debug, serde::Deserialize)]
+/// struct Family {
+///     #[allow(dead_code)]
+///     children: Vec<Child>,
+/// }
+///
+/// let error = de::from_str::<Family>(r#"{"children": [{"age": 3}, {"age": "3"}]}"#)
+///     .unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     "INVALID_ARGUMENT: Invalid request (bad request: \
+///      children[1].age: invalid type: string \"3\", expected u32)"
+/// );
+/// ```
+#[cfg(feature = "with-serde-json")]
+pub fn from_str<'de, T: de::Deserialize<'de>>(s: &'de str) -> Result<T> {
+    from_json(serde_json::Deserializer::from_str(s))
+}
+
+/// Deserializes a `T` from JSON bytes, reporting the path of a failure, see
+/// [`from_str`].
+#[cfg(feature = "with-serde-json")]
+pub fn from_slice<'de, T: de::Deserialize<'de>>(v: &'de [u8]) -> Result<T> {
+    from_json(serde_json::Deserializer::from_slice(v))
+}
+
+#[cfg(feature = "with-serde-json")]
+fn from_json<'de, R, T>(mut deserializer: serde_json::Deserializer<R>) -> Result<T>
+where
+    R: serde_json::de::Read<'de>,
+    T: de::Deserialize<'de>,
+{
+    let track = Track::default();
+    let value = T::deserialize(Tracked::new(&mut deserializer, &track))
+        .and_then(|value| deserializer.end().map(|()| value));
+    value.map_err(|err| {
+        if !err.is_data() {
+            return Error::from(err);
+        }
+        // The message ends with the location, which is superseded by the path.
+        let location = format!(" at line {} column {}", err.line(), err.column());
+        let description = err.to_string();
+        let description = description
+            .strip_suffix(&location)
+            .unwrap_or(&description)
+            .to_owned();
+        track.into_error(description, err)
+    })
+}
+
+/// A step of the path to the value being deserialized.
+#[derive(Clone, Debug)]
+enum Segment {
+    Member(String),
+    Key(String),
+    Index(usize),
+}
+
+/// Whether the keys of a map are struct members or map keys.
+#[derive(Clone, Copy)]
+enum Keys {
+    Member,
+    Key,
+}
+
+impl Keys {
+    fn segment(self, key: String) -> Segment {
+        match self {
+            Keys::Member => Segment::Member(key),
+            Keys::Key => Segment::Key(key),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Track {
+    /// The path to the value being deserialized.
+    path: RefCell<Vec<Segment>>,
+    /// The last map key or enum variant deserialized.
+    key: RefCell<Option<String>>,
+    /// The path at the deepest failure.
+    failed: RefCell<Option<Vec<Segment>>>,
+}
+
+impl Track {
+    /// Runs `f` with `segment` appended to the path.
+    fn enter<T, E, F: FnOnce() -> std::result::Result<T, E>>(
+        &self,
+        segment: Segment,
+        f: F,
+    ) -> std::result::Result<T, E> {
+        self.path.borrow_mut().push(segment);
+        let result = self.check(f());
+        self.path.borrow_mut().pop();
+        result
+    }
+
+    /// Records the path of a failure unless a deeper one was recorded.
+    fn check<T, E>(&self, result: std::result::Result<T, E>) -> std::result::Result<T, E> {
+        if result.is_err() {
+            let mut failed = self.failed.borrow_mut();
+            if failed.is_none() {
+                *failed = Some(self.path.borrow().clone());
+            }
+        }
+        result
+    }
+
+    /// Records the path of a missing `field` of the map being deserialized
+    /// unless a deeper failure was recorded.
+    fn missing<E: de::Error>(&self, field: &'static str) -> E {
+        let mut failed = self.failed.borrow_mut();
+        if failed.is_none() {
+            let mut path = self.path.borrow().clone();
+            path.push(Segment::Member(field.to_owned()));
+            *failed = Some(path);
+        }
+        E::missing_field(field)
+    }
+
+    fn field(self) -> Option<Field> {
+        let path = self.failed.into_inner()?;
+        let mut properties: Vec<Property> = Vec::with_capacity(path.len());
+        for segment in path {
+            let property = match (segment, properties.pop()) {
+                (Segment::Index(index), Some(Property::Member { name })) => {
+                    Property::ArrayMember { name, index }
+                }
+                (Segment::Key(key), Some(Property::Member { name })) => {
+                    Property::MapMember { name, key }
+                }
+                (segment, parent) => {
+                    properties.extend(parent);
+                    let name = match segment {
+                        Segment::Member(name) | Segment::Key(name) => name,
+                        Segment::Index(index) => index.to_string(),
+                    };
+                    Property::Member { name }
+                }
+            };
+            properties.push(property);
+        }
+        let mut properties = properties.into_iter().rev();
+        let field = Field::new(properties.next()?);
+        Some(properties.fold(field, Field::with_context))
+    }
+
+    fn into_error<E: std::error::Error + Send + Sync + 'static>(
+        self,
+        description: String,
+        source: E,
+    ) -> Error {
+        let status = ErrorStatus::captured().with_message("Invalid request");
+        let status = match self.field() {
+            Some(field) => status.with_field_violation(FieldViolation {
+                field,
+                description: Some(description),
+            }),
+            None => status,
+        };
+        Error::new(Code::InvalidArgument, status.with_error(source))
+    }
+}
+
+/// A deserializer that tracks the path of the values it deserializes.
+struct Tracked<'t, D> {
+    inner: D,
+    track: &'t Track,
+    /// Whether a scalar is captured as the key of a map entry or variant,
+    /// whose failure is recorded by the map or enum access.
+    capture: bool,
+}
+
+impl<'t, D> Tracked<'t, D> {
+    fn new(inner: D, track: &'t Track) -> Self {
+        Tracked {
+            inner,
+            track,
+            capture: false,
+        }
+    }
+
+    /// Deserializes with `f` from the inner deserializer, recording the path
+    /// of a failure unless the value is captured.
+    fn forward<'de, V, F>(
+        self,
+        visitor: V,
+        keys: Keys,
+        f: F,
+    ) -> std::result::Result<V::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+        V: Visitor<'de>,
+        F: FnOnce(D, TrackedVisitor<'t, V>) -> std::result::Result<V::Value, D::Error>,
+    {
+        let visitor = TrackedVisitor {
+            inner: visitor,
+            track: self.track,
+            capture: self.capture,
+            keys,
+        };
+        let result = f(self.inner, visitor);
+        if self.capture {
+            result
+        } else {
+            self.track.check(result)
+        }
+    }
+}
+
+impl<'de, 't, D: de::Deserializer<'de>> de::Deserializer<'de> for Tracked<'t, D> {
+    type Error = D::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_any(visitor)
+        })
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_bool(visitor)
+        })
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_i8(visitor)
+        })
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_i16(visitor)
+        })
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_i32(visitor)
+        })
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_i64(visitor)
+        })
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_i128(visitor)
+        })
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_u8(visitor)
+        })
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_u16(visitor)
+        })
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_u32(visitor)
+        })
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_u64(visitor)
+        })
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_u128(visitor)
+        })
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_f32(visitor)
+        })
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_f64(visitor)
+        })
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_char(visitor)
+        })
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_str(visitor)
+        })
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_string(visitor)
+        })
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_bytes(visitor)
+        })
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_byte_buf(visitor)
+        })
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_option(visitor)
+        })
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_unit(visitor)
+        })
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_unit_struct(name, visitor)
+        })
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_newtype_struct(name, visitor)
+        })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_seq(visitor)
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_tuple(len, visitor)
+        })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_tuple_struct(name, len, visitor)
+        })
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Key, |inner, visitor| {
+            inner.deserialize_map(visitor)
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_struct(name, fields, visitor)
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_enum(name, variants, visitor)
+        })
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_identifier(visitor)
+        })
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, D::Error> {
+        self.forward(visitor, Keys::Member, |inner, visitor| {
+            inner.deserialize_ignored_any(visitor)
+        })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+struct TrackedSeed<'t, S> {
+    inner: S,
+    track: &'t Track,
+    capture: bool,
+}
+
+impl<'de, 't, S: DeserializeSeed<'de>> DeserializeSeed<'de> for TrackedSeed<'t, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        self.inner.deserialize(Tracked {
+            inner: deserializer,
+            track: self.track,
+            capture: self.capture,
+        })
+    }
+}
+
+struct TrackedVisitor<'t, V> {
+    inner: V,
+    track: &'t Track,
+    capture: bool,
+    keys: Keys,
+}
+
+impl<'t, V> TrackedVisitor<'t, V> {
+    fn capture<K: ToString>(&self, key: K) {
+        if self.capture {
+            *self.track.key.borrow_mut() = Some(key.to_string());
+        }
+    }
+
+    fn tracked<D>(&self, inner: D) -> Tracked<'t, D> {
+        Tracked::new(inner, self.track)
+    }
+}
+
+impl<'de, 't, V: Visitor<'de>> Visitor<'de> for TrackedVisitor<'t, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(formatter)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_bool(v)
+    }
+
+    fn visit_i8<E: de::Error>(self, v: i8) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_i8(v)
+    }
+
+    fn visit_i16<E: de::Error>(self, v: i16) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_i16(v)
+    }
+
+    fn visit_i32<E: de::Error>(self, v: i32) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_i32(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_i64(v)
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_i128(v)
+    }
+
+    fn visit_u8<E: de::Error>(self, v: u8) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_u8(v)
+    }
+
+    fn visit_u16<E: de::Error>(self, v: u16) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_u16(v)
+    }
+
+    fn visit_u32<E: de::Error>(self, v: u32) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_u32(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_u64(v)
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_u128(v)
+    }
+
+    fn visit_f32<E: de::Error>(self, v: f32) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_f32(v)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_f64(v)
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_char(v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_str(v)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> std::result::Result<Self::Value, E> {
+        self.capture(v);
+        self.inner.visit_borrowed_str(v)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> std::result::Result<Self::Value, E> {
+        self.capture(&v);
+        self.inner.visit_string(v)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> std::result::Result<Self::Value, E> {
+        self.inner.visit_bytes(v)
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(
+        self,
+        v: &'de [u8],
+    ) -> std::result::Result<Self::Value, E> {
+        self.inner.visit_borrowed_bytes(v)
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> std::result::Result<Self::Value, E> {
+        self.inner.visit_byte_buf(v)
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        let deserializer = self.tracked(deserializer);
+        self.inner.visit_some(deserializer)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        let deserializer = self.tracked(deserializer);
+        self.inner.visit_newtype_struct(deserializer)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(
+        self,
+        seq: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        self.inner.visit_seq(TrackedSeq {
+            inner: seq,
+            track: self.track,
+            index: 0,
+        })
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(
+        self,
+        map: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let track = self.track;
+        self.inner
+            .visit_map(TrackedMap {
+                inner: map,
+                track,
+                keys: self.keys,
+                key: None,
+            })
+            .map_err(|err| err.into_inner(track))
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(
+        self,
+        data: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        self.inner.visit_enum(TrackedEnum {
+            inner: data,
+            track: self.track,
+        })
+    }
+}
+
+struct TrackedSeq<'t, A> {
+    inner: A,
+    track: &'t Track,
+    index: usize,
+}
+
+impl<'de, 't, A: de::SeqAccess<'de>> de::SeqAccess<'de> for TrackedSeq<'t, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> std::result::Result<Option<T::Value>, A::Error> {
+        let track = self.track;
+        let inner = &mut self.inner;
+        let segment = Segment::Index(self.index);
+        self.index += 1;
+        track.enter(segment, || {
+            inner.next_element_seed(TrackedSeed {
+                inner: seed,
+                track,
+                capture: false,
+            })
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+struct TrackedMap<'t, A> {
+    inner: A,
+    track: &'t Track,
+    keys: Keys,
+    key: Option<String>,
+}
+
+impl<'de, 't, A: de::MapAccess<'de>> de::MapAccess<'de> for TrackedMap<'t, A> {
+    type Error = MapError<A::Error>;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> std::result::Result<Option<K::Value>, Self::Error> {
+        let track = self.track;
+        track.key.take();
+        let result = self.inner.next_key_seed(TrackedSeed {
+            inner: seed,
+            track,
+            capture: true,
+        });
+        self.key = track.key.take();
+        let result = match (&result, &self.key) {
+            (Err(_), Some(key)) => track.enter(self.keys.segment(key.clone()), || result),
+            _ => track.check(result),
+        };
+        result.map_err(MapError::Inner)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        let track = self.track;
+        let seed = TrackedSeed {
+            inner: seed,
+            track,
+            capture: false,
+        };
+        let result = match self.key.take() {
+            Some(key) => track.enter(self.keys.segment(key), || self.inner.next_value_seed(seed)),
+            None => track.check(self.inner.next_value_seed(seed)),
+        };
+        result.map_err(MapError::Inner)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+/// The error of a [`TrackedMap`], which keeps a missing field reported by the
+/// visitor of the map apart so that its path can be recorded.
+#[derive(Debug)]
+enum MapError<E> {
+    Inner(E),
+    MissingField(&'static str),
+}
+
+impl<E: de::Error> MapError<E> {
+    fn into_inner(self, track: &Track) -> E {
+        match self {
+            MapError::Inner(err) => err,
+            MapError::MissingField(field) => track.missing(field),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for MapError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Inner(err) => err.fmt(f),
+            MapError::MissingField(field) => write!(f, "missing field `{field}`"),
+        }
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for MapError<E> {}
+
+impl<E: de::Error> de::Error for MapError<E> {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        MapError::Inner(E::custom(msg))
+    }
+
+    fn invalid_type(unexp: de::Unexpected, exp: &dyn de::Expected) -> Self {
+        MapError::Inner(E::invalid_type(unexp, exp))
+    }
+
+    fn invalid_value(unexp: de::Unexpected, exp: &dyn de::Expected) -> Self {
+        MapError::Inner(E::invalid_value(unexp, exp))
+    }
+
+    fn invalid_length(len: usize, exp: &dyn de::Expected) -> Self {
+        MapError::Inner(E::invalid_length(len, exp))
+    }
+
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        MapError::Inner(E::unknown_variant(variant, expected))
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        MapError::Inner(E::unknown_field(field, expected))
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        MapError::MissingField(field)
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        MapError::Inner(E::duplicate_field(field))
+    }
+}
+
+struct TrackedEnum<'t, A> {
+    inner: A,
+    track: &'t Track,
+}
+
+impl<'de, 't, A: de::EnumAccess<'de>> de::EnumAccess<'de> for TrackedEnum<'t, A> {
+    type Error = A::Error;
+    type Variant = TrackedVariant<'t, A::Variant>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> std::result::Result<(V::Value, Self::Variant), A::Error> {
+        let track = self.track;
+        track.key.take();
+        let (value, variant) = track.check(self.inner.variant_seed(TrackedSeed {
+            inner: seed,
+            track,
+            capture: true,
+        }))?;
+        Ok((
+            value,
+            TrackedVariant {
+                inner: variant,
+                track,
+                name: track.key.take(),
+            },
+        ))
+    }
+}
+
+struct TrackedVariant<'t, A> {
+    inner: A,
+    track: &'t Track,
+    name: Option<String>,
+}
+
+impl<'t, A> TrackedVariant<'t, A> {
+    fn enter<T, E, F: FnOnce(A) -> std::result::Result<T, E>>(
+        self,
+        f: F,
+    ) -> std::result::Result<T, E> {
+        let inner = self.inner;
+        match self.name {
+            Some(name) => self.track.enter(Segment::Member(name), || f(inner)),
+            None => self.track.check(f(inner)),
+        }
+    }
+
+    fn visitor<V>(&self, inner: V) -> TrackedVisitor<'t, V> {
+        TrackedVisitor {
+            inner,
+            track: self.track,
+            capture: false,
+            keys: Keys::Member,
+        }
+    }
+}
+
+impl<'de, 't, A: de::VariantAccess<'de>> de::VariantAccess<'de> for TrackedVariant<'t, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> std::result::Result<(), A::Error> {
+        self.enter(A::unit_variant)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> std::result::Result<T::Value, A::Error> {
+        let seed = TrackedSeed {
+            inner: seed,
+            track: self.track,
+            capture: false,
+        };
+        self.enter(|inner| inner.newtype_variant_seed(seed))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, A::Error> {
+        let visitor = self.visitor(visitor);
+        self.enter(|inner| inner.tuple_variant(len, visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, A::Error> {
+        let visitor = self.visitor(visitor);
+        self.enter(|inner| inner.struct_variant(fields, visitor))
+    }
+}
+
+#[cfg(all(test, feature = "with-serde-json"))]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::ErrorDetails;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Child {
+        name: String,
+        #[serde(default)]
+        nicknames: BTreeMap<String, u32>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Family {
+        children: Vec<Child>,
+        pet: Option<Pet>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    enum Pet {
+        Dog { age: u32 },
+    }
+
+    fn field_violation(json: &str) -> (String, String) {
+        let error = from_str::<Family>(json).unwrap_err();
+        assert_eq!(error.code(), Code::InvalidArgument);
+        match error.inner().details.as_deref() {
+            Some([ErrorDetails::BadRequest { field_violations }]) => (
+                field_violations[0].field.to_string(),
+                field_violations[0].description.clone().unwrap_or_default(),
+            ),
+            other => panic!("unexpected details: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_path_of_invalid_value() {
+        assert_eq!(
+            field_violation(r#"{"children": [{"name": "Kris"}, {"name": 1}]}"#),
+            (
+                "children[1].name".to_string(),
+                "invalid type: integer `1`, expected a string".to_string()
+            )
+        );
+        assert_eq!(
+            field_violation(r#"{"children": [{"name": "Kris", "nicknames": {"joe": -1}}]}"#).0,
+            r#"children[0].nicknames["joe"]"#
+        );
+        assert_eq!(
+            field_violation(r#"{"children": [], "pet": {"Dog": {"age": "3"}}}"#).0,
+            "pet.Dog.age"
+        );
+    }
+
+    #[test]
+    fn reports_path_of_missing_and_unknown_fields() {
+        assert_eq!(
+            field_violation(r#"{"children": [{}]}"#),
+            (
+                "children[0].name".to_string(),
+                "missing field `name`".to_string()
+            )
+        );
+        assert_eq!(
+            field_violation(r#"{"children": [], "pet": {"Dog": {}}}"#),
+            ("pet.Dog.age".to_string(), "missing field `age`".to_string())
+        );
+        assert_eq!(field_violation(r#"{"children": [], "age": 3}"#).0, "age");
+    }
+
+    #[test]
+    fn reports_syntax_errors_by_location() {
+        let error = from_str::<Family>("{\"children\": [}").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "INVALID_ARGUMENT: Invalid JSON: expected value at line 1 column 15"
+        );
+    }
+
+    #[test]
+    fn deserializes_valid_requests() {
+        let family: Family =
+            from_slice(br#"{"children": [{"name": "Kris", "nicknames": {"k": 1}}]}"#)
+                .expect("valid family");
+        assert_eq!(family.children[0].nicknames.get("k"), Some(&1));
+    }
+}
@@ -0,0 +1,268 @@
+//! Process exit codes of errors for command line tools, following the
+//! sysexits.h conventions so that scripts can tell, e.g., bad usage apart
+//! from an unavailable service.
+//!
+//! | Code                                                 | Exit code | sysexits.h       |
+//! | :--------------------------------------------------- | --------: | :--------------- |
+//! | `INVALID_ARGUMENT`                                   |        64 | `EX_USAGE`       |
+//! | `OUT_OF_RANGE`, `DATA_LOSS`                          |        65 | `EX_DATAERR`     |
+//! | `NOT_FOUND`                                          |        66 | `EX_NOINPUT`     |
+//! | `UNAVAILABLE`                                        |        69 | `EX_UNAVAILABLE` |
+//! | `UNKNOWN`, `INTERNAL`, `UNIMPLEMENTED`               |        70 | `EX_SOFTWARE`    |
+//! | `ALREADY_EXISTS`                                     |        73 | `EX_CANTCREAT`   |
+//! | `DEADLINE_EXCEEDED`, `RESOURCE_EXHAUSTED`, `ABORTED` |        75 | `EX_TEMPFAIL`    |
+//! | `PERMISSION_DENIED`, `UNAUTHENTICATED`               |        77 | `EX_NOPERM`      |
+//! | `FAILED_PRECONDITION`                                |        78 | `EX_CONFIG`      |
+//! | `CANCELLED`                                          |       130 | Interrupted      |
+//!
+//! An [`Exit`] returned from `main` reports the error on standard error in
+//! the [`Output`] format, e.g., the human form:
+//!
+//! ```text
+//! Error: Invalid greeting (INVALID_ARGUMENT)
+//!   recipients[1].name: Too long
+//!   Nom trop long
+//!   See Greeting rules: https://greeter.appbiotic.com/rules
+//!   Caused by: Name has 300 characters
+//! ```
+//!
+//! ```no_run
+//! use appbiotic_code_error::{exit::Exit, Error};
+//!
+//! fn run() -> appbiotic_code_error::Result<()> {
+//!     Err(Error::unavailable("Greeter is down"))
+//! }
+//!
+//! fn main() -> Exit {
+//!     run().into()
+//! }
+//! ```
+
+use std::{
+    fmt::Write,
+    process::{ExitCode, Termination},
+};
+
+use crate::{Code, Error, ErrorDetails};
+
+pub const SUCCESS: u8 = 0;
+pub const USAGE: u8 = 64;
+pub const DATA_ERR: u8 = 65;
+pub const NO_INPUT: u8 = 66;
+pub const UNAVAILABLE: u8 = 69;
+pub const SOFTWARE: u8 = 70;
+pub const CANT_CREAT: u8 = 73;
+pub const TEMP_FAIL: u8 = 75;
+pub const NO_PERM: u8 = 77;
+pub const CONFIG: u8 = 78;
+/// The exit code of a process interrupted by `SIGINT`, i.e., 128 + 2.
+pub const INTERRUPTED: u8 = 130;
+
+impl Code {
+    /// Returns the process exit code of the code, see the
+    /// [`exit`](crate::exit) module documentation.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Code::InvalidArgument => USAGE,
+            Code::OutOfRange | Code::DataLoss => DATA_ERR,
+            Code::NotFound => NO_INPUT,
+            Code::Unavailable => UNAVAILABLE,
+            Code::Unknown | Code::Internal | Code::Unimplemented => SOFTWARE,
+            Code::AlreadyExists => CANT_CREAT,
+            Code::DeadlineExceeded | Code::ResourceExhausted | Code::Aborted => TEMP_FAIL,
+            Code::PermissionDenied | Code::Unauthenticated => NO_PERM,
+            Code::FailedPrecondition => CONFIG,
+            Code::Cancelled => INTERRUPTED,
+        }
+    }
+}
+
+impl Error {
+    /// Returns the process exit code of the error's code.
+    pub fn exit_code(&self) -> u8 {
+        self.code().exit_code()
+    }
+}
+
+/// The format errors are reported in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Output {
+    /// The message, localized messages, field violations, help links and
+    /// causes of the error, for terminals.
+    #[default]
+    Human,
+    /// The `google.rpc.Status` JSON form, for scripts.
+    #[cfg(feature = "with-serde-json")]
+    Json,
+}
+
+impl Output {
+    /// Renders `error` in the format.
+    pub fn render(self, error: &Error) -> String {
+        match self {
+            Output::Human => human(error),
+            #[cfg(feature = "with-serde-json")]
+            Output::Json => serde_json::to_string_pretty(error).unwrap_or_default(),
+        }
+    }
+}
+
+fn human(error: &Error) -> String {
+    let status = error.inner();
+    let mut report = format!(
+        "Error: {} ({})",
+        status.message.as_deref().unwrap_or(error.code().as_str()),
+        error.code()
+    );
+    let mut line = |text: std::fmt::Arguments| {
+        let _ = write!(report, "\n  {text}");
+    };
+    for details in status.details.iter().flatten() {
+        match details {
+            ErrorDetails::LocalizedMessage { message, .. } => line(format_args!("{message}")),
+            ErrorDetails::BadRequest { field_violations } => {
+                for violation in field_violations {
+                    match &violation.description {
+                        Some(description) => {
+                            line(format_args!("{}: {description}", violation.field))
+                        }
+                        None => line(format_args!("{}", violation.field)),
+                    }
+                }
+            }
+            ErrorDetails::Help { links } => {
+                for link in links {
+                    match &link.description {
+                        Some(description) => line(format_args!("See {description}: {}", link.url)),
+                        None => line(format_args!("See {}", link.url)),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut cause = status
+        .source()
+        .map(|source| source as &dyn std::error::Error);
+    while let Some(error) = cause {
+        line(format_args!("Caused by: {error}"));
+        cause = error.source();
+    }
+    report
+}
+
+/// The result of a command line tool, to be returned from `main`.
+///
+/// An error is reported on standard error in the [`Output`] format and exits
+/// the process with the [`Error::exit_code`].
+#[derive(Debug)]
+pub struct Exit {
+    result: crate::Result<()>,
+    output: Output,
+}
+
+impl Exit {
+    pub fn new(result: crate::Result<()>) -> Self {
+        Exit {
+            result,
+            output: Output::default(),
+        }
+    }
+
+    pub fn with_output(self, output: Output) -> Self {
+        Exit { output, ..self }
+    }
+}
+
+impl<E: Into<Error>> From<Result<(), E>> for Exit {
+    fn from(value: Result<(), E>) -> Self {
+        Exit::new(value.map_err(Into::into))
+    }
+}
+
+impl Termination for Exit {
+    fn report(self) -> ExitCode {
+        match self.result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{}", self.output.render(&error));
+                ExitCode::from(error.exit_code())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(Error::invalid_argument("Bad usage").exit_code(), 64);
+        assert_eq!(Error::not_found("No greeting").exit_code(), 66);
+        assert_eq!(Error::unavailable("Service down").exit_code(), 69);
+        assert_eq!(Error::permission_denied("Denied").exit_code(), 77);
+        for code in Code::ALL {
+            assert_ne!(code.exit_code(), SUCCESS, "{code}");
+        }
+    }
+
+    #[test]
+    fn exit_report() {
+        assert_eq!(Exit::new(Ok(())).report(), ExitCode::SUCCESS);
+        assert_eq!(
+            Exit::from(Err::<(), _>(Error::unavailable("Service down"))).report(),
+            ExitCode::from(UNAVAILABLE)
+        );
+    }
+
+    fn invalid_greeting() -> Error {
+        Error::invalid_argument("Invalid greeting")
+            .with_details(ErrorDetails::bad_request(crate::FieldViolation {
+                field: crate::Field::new(crate::Property::Member {
+                    name: "name".to_string(),
+                })
+                .with_context(crate::Property::ArrayMember {
+                    name: "recipients".to_string(),
+                    index: 1,
+                }),
+                description: Some("Too long".to_string()),
+            }))
+            .with_details(ErrorDetails::localized_message("fr-CA", "Nom trop long"))
+            .with_details(ErrorDetails::help(crate::Link {
+                description: Some("Greeting rules".to_string()),
+                url: "https://greeter.appbiotic.com/rules".to_string(),
+            }))
+            .with_details(ErrorDetails::debug_info("Checked by greeter::validate"))
+            .with_error("Name has 300 characters")
+    }
+
+    #[test]
+    fn human_output() {
+        assert_eq!(
+            Output::Human.render(&invalid_greeting()),
+            "Error: Invalid greeting (INVALID_ARGUMENT)
+  recipients[1].name: Too long
+  Nom trop long
+  See Greeting rules: https://greeter.appbiotic.com/rules
+  Caused by: Name has 300 characters"
+        );
+        assert_eq!(
+            Output::Human.render(&Error::new(Code::NotFound, Default::default())),
+            "Error: NOT_FOUND (NOT_FOUND)"
+        );
+    }
+
+    #[cfg(feature = "with-serde-json")]
+    #[test]
+    fn json_output() {
+        let json: serde_json::Value =
+            serde_json::from_str(&Output::Json.render(&invalid_greeting())).expect("JSON output");
+        assert_eq!(json["code"], 3);
+        assert_eq!(json["message"], "Invalid greeting");
+        assert_eq!(
+            json["details"][0]["fieldViolations"][0]["field"],
+            "recipients[1].name"
+        );
+    }
+}
@@ -0,0 +1,383 @@
+//! Extension traits to turn foreign errors and missing values into an
+//! [`Error`] with a code and a message describing what was being done.
+//!
+//! ```
+//! use appbiotic_code_error::{Code, OptionExt, Property, ResultExt};
+//!
+//! fn port(config: &str) -> appbiotic_code_error::Result<u16> {
+//!     let value = config
+//!         .strip_prefix("port=")
+//!         .or_not_found("Missing port setting")?;
+//!     value.parse().or_invalid_argument_at(
+//!         Property::Member {
+//!             name: "port".to_string(),
+//!         },
+//!         "Must be a port number",
+//!     )
+//! }
+//!
+//! assert_eq!(port("port=8080").unwrap(), 8080);
+//! assert_eq!(port("host=localhost").unwrap_err().code(), Code::NotFound);
+//! assert_eq!(
+//!     port("port=http").unwrap_err().to_string(),
+//!     "INVALID_ARGUMENT: Invalid request (bad request: port: Must be a port number)"
+//! );
+//! ```
+
+use std::borrow::Cow;
+
+use crate::{BoxError, Code, Error, ErrorStatus, Field, FieldViolation, Result};
+
+/// Maps the error of a `Result` into an [`Error`] that keeps it as the cause.
+///
+/// The `*_with` variants only build the message when there is an error.
+pub trait ResultExt<T>: Sized {
+    /// Maps the error into an error with `code` and the message returned by
+    /// `message`.
+    fn or_code_with<S, F>(self, code: Code, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S;
+
+    /// Maps the error into an error with `code` and `message`.
+    fn or_code<S: Into<Cow<'static, str>>>(self, code: Code, message: S) -> Result<T> {
+        self.or_code_with(code, || message)
+    }
+
+    /// Maps the error into a [`Code::InvalidArgument`] error with a
+    /// `ErrorDetails::BadRequest` for `field`, as [`Validator`] reports it.
+    ///
+    /// [`Validator`]: crate::validate::Validator
+    fn or_invalid_argument_at<F, D>(self, field: F, description: D) -> Result<T>
+    where
+        F: Into<Field>,
+        D: AsRef<str>;
+
+    fn or_invalid_argument<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::InvalidArgument, message)
+    }
+
+    fn or_invalid_argument_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::InvalidArgument, message)
+    }
+
+    fn or_not_found<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::NotFound, message)
+    }
+
+    fn or_not_found_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::NotFound, message)
+    }
+
+    fn or_failed_precondition<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::FailedPrecondition, message)
+    }
+
+    fn or_failed_precondition_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::FailedPrecondition, message)
+    }
+
+    fn or_unavailable<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::Unavailable, message)
+    }
+
+    fn or_unavailable_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::Unavailable, message)
+    }
+
+    fn or_internal<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::Internal, message)
+    }
+
+    fn or_internal_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::Internal, message)
+    }
+}
+
+impl<T, E: Into<BoxError>> ResultExt<T> for std::result::Result<T, E> {
+    fn or_code_with<S, F>(self, code: Code, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.map_err(|err| Error::with_code(code, message()).with_error(err))
+    }
+
+    fn or_invalid_argument_at<F, D>(self, field: F, description: D) -> Result<T>
+    where
+        F: Into<Field>,
+        D: AsRef<str>,
+    {
+        self.map_err(|err| invalid_argument_at(field.into(), description).with_error(err))
+    }
+}
+
+/// Turns a missing value of an `Option` into an [`Error`].
+///
+/// The `*_with` variants only build the message when the value is missing.
+pub trait OptionExt<T>: Sized {
+    /// Turns a missing value into an error with `code` and the message
+    /// returned by `message`.
+    fn or_code_with<S, F>(self, code: Code, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S;
+
+    /// Turns a missing value into an error with `code` and `message`.
+    fn or_code<S: Into<Cow<'static, str>>>(self, code: Code, message: S) -> Result<T> {
+        self.or_code_with(code, || message)
+    }
+
+    /// Turns a missing value into a [`Code::InvalidArgument`] error with a
+    /// `ErrorDetails::BadRequest` for `field`, as [`Validator`] reports it.
+    ///
+    /// [`Validator`]: crate::validate::Validator
+    fn or_invalid_argument_at<F, D>(self, field: F, description: D) -> Result<T>
+    where
+        F: Into<Field>,
+        D: AsRef<str>;
+
+    fn or_invalid_argument<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::InvalidArgument, message)
+    }
+
+    fn or_invalid_argument_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::InvalidArgument, message)
+    }
+
+    fn or_not_found<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::NotFound, message)
+    }
+
+    fn or_not_found_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::NotFound, message)
+    }
+
+    fn or_failed_precondition<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::FailedPrecondition, message)
+    }
+
+    fn or_failed_precondition_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::FailedPrecondition, message)
+    }
+
+    fn or_unavailable<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::Unavailable, message)
+    }
+
+    fn or_unavailable_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::Unavailable, message)
+    }
+
+    fn or_internal<S: Into<Cow<'static, str>>>(self, message: S) -> Result<T> {
+        self.or_code(Code::Internal, message)
+    }
+
+    fn or_internal_with<S, F>(self, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.or_code_with(Code::Internal, message)
+    }
+}
+
+impl<T> OptionExt<T> for Option<T> {
+    fn or_code_with<S, F>(self, code: Code, message: F) -> Result<T>
+    where
+        S: Into<Cow<'static, str>>,
+        F: FnOnce() -> S,
+    {
+        self.ok_or_else(|| Error::with_code(code, message()))
+    }
+
+    fn or_invalid_argument_at<F, D>(self, field: F, description: D) -> Result<T>
+    where
+        F: Into<Field>,
+        D: AsRef<str>,
+    {
+        self.ok_or_else(|| invalid_argument_at(field.into(), description))
+    }
+}
+
+fn invalid_argument_at<D: AsRef<str>>(field: Field, description: D) -> Error {
+    Error::new(
+        Code::InvalidArgument,
+        ErrorStatus::captured()
+            .with_message("Invalid request")
+            .with_field_violation(FieldViolation {
+                field,
+                description: Some(description.as_ref().to_owned()),
+            }),
+    )
+}
+
+#[cfg(feature = "with-anyhow")]
+impl From<anyhow::Error> for Error {
+    /// Recovers the [`Error`] that was converted into an `anyhow::Error`,
+    /// keeping its code and using the outermost context as the message.
+    /// Errors without an [`Error`] in their chain become [`Code::Unknown`].
+    ///
+    /// An [`Error`] converts into an `anyhow::Error` with `?` like any other
+    /// error.
+    fn from(mut value: anyhow::Error) -> Self {
+        // A context wraps the error but still downcasts to it, so only an
+        // error without context is returned as is.
+        if value
+            .chain()
+            .next()
+            .is_some_and(|cause| cause.is::<Error>())
+        {
+            match value.downcast::<Error>() {
+                Ok(error) => return error,
+                Err(error) => value = error,
+            }
+        }
+        let code = value
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<Error>())
+            .map_or(Code::Unknown, Error::code);
+        let message = value.to_string();
+        Error::new(
+            code,
+            ErrorStatus::captured()
+                .with_message(message)
+                .with_error(value),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::ParseIntError;
+
+    use super::*;
+    use crate::Property;
+
+    fn port() -> Property {
+        Property::Member {
+            name: "port".to_string(),
+        }
+    }
+
+    #[test]
+    fn result_or_code() {
+        let error = "http"
+            .parse::<u16>()
+            .or_internal("Failed to load config")
+            .unwrap_err();
+        assert_eq!(error.code(), Code::Internal);
+        assert_eq!(error.to_string(), "INTERNAL: Failed to load config");
+        assert!(error.find_source::<ParseIntError>().is_some());
+
+        let mut called = false;
+        let value = "80".parse::<u16>().or_not_found_with(|| {
+            called = true;
+            "Not called"
+        });
+        assert_eq!(value.unwrap(), 80);
+        assert!(!called);
+    }
+
+    #[test]
+    fn result_or_invalid_argument_at() {
+        let error = "http"
+            .parse::<u16>()
+            .or_invalid_argument_at(Field::new(port()).with_context(port()), "Must be a number")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "INVALID_ARGUMENT: Invalid request (bad request: port.port: Must be a number)"
+        );
+        assert!(error.find_source::<ParseIntError>().is_some());
+    }
+
+    #[test]
+    fn option_or_code() {
+        let error = None::<u16>
+            .or_failed_precondition_with(|| format!("Greeter {} is closed", 3))
+            .unwrap_err();
+        assert_eq!(error.code(), Code::FailedPrecondition);
+        assert_eq!(
+            error.to_string(),
+            "FAILED_PRECONDITION: Greeter 3 is closed"
+        );
+
+        let error = None::<u16>
+            .or_invalid_argument_at(port(), "Required")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "INVALID_ARGUMENT: Invalid request (bad request: port: Required)"
+        );
+        assert_eq!(
+            None::<u16>
+                .or_unavailable("No greeter connected")
+                .unwrap_err()
+                .code(),
+            Code::Unavailable
+        );
+        assert_eq!(Some(1).or_unavailable_with(|| "Unused").ok(), Some(1));
+    }
+
+    #[cfg(feature = "with-anyhow")]
+    #[test]
+    fn anyhow_round_trip() {
+        use anyhow::Context;
+
+        fn load() -> anyhow::Result<()> {
+            Err(Error::not_found("Greeting not found"))?
+        }
+
+        let error = Error::from(load().unwrap_err());
+        assert_eq!(error.to_string(), "NOT_FOUND: Greeting not found");
+
+        let error = Error::from(load().context("Loading greetings").unwrap_err());
+        assert_eq!(error.code(), Code::NotFound);
+        assert_eq!(error.to_string(), "NOT_FOUND: Loading greetings");
+        assert_eq!(
+            error.inner().encoded_details()[0].to_string(),
+            "debug info: Loading greetings: NOT_FOUND: Greeting not found"
+        );
+
+        let error = Error::from(anyhow::anyhow!("Disk full"));
+        assert_eq!(error.code(), Code::Unknown);
+        assert_eq!(error.to_string(), "UNKNOWN: Disk full");
+    }
+}
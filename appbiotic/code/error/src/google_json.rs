@@ -0,0 +1,223 @@
+//! The JSON error envelope returned by HTTP-transcoded Google APIs, see
+//! https://google.aip.dev/193#http11json-representation.
+//!
+//! ```json
+//! {
+//!   "error": {
+//!     "code": 404,
+//!     "message": "Greeting not found",
+//!     "status": "NOT_FOUND",
+//!     "details": []
+//!   }
+//! }
+//! ```
+//!
+//! Unlike `google.rpc.Status`, `code` holds the HTTP status code and the gRPC
+//! code is carried by its name in `status`.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{redact::Redactor, Code, Error, ErrorDetails, ErrorStatus, Result};
+
+/// The media type of the JSON error envelope.
+pub const CONTENT_TYPE: &str = "application/json";
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    error: EnvelopeError,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EnvelopeError {
+    #[serde(default)]
+    code: u16,
+    #[serde(default)]
+    message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    details: Vec<Value>,
+}
+
+/// Encodes `error` as a JSON error envelope.
+pub fn encode(error: &Error) -> Vec<u8> {
+    let envelope = Envelope {
+        error: EnvelopeError {
+            code: error.http_status().as_u16(),
+            message: error
+                .inner()
+                .message
+                .as_deref()
+                .unwrap_or_default()
+                .to_owned(),
+            status: Some(error.code().as_str().to_owned()),
+            details: error
+                .inner()
+                .encoded_details()
+                .iter()
+                .map(|details| serde_json::to_value(details).unwrap_or_default())
+                .collect(),
+        },
+    };
+    serde_json::to_vec(&envelope).unwrap_or_default()
+}
+
+/// Decodes a JSON error envelope into an [`Error`].
+///
+/// The gRPC code is taken from `status`, or from the HTTP status in `code`
+/// when `status` is absent. Details that are not known `google.rpc` error
+/// details or fail to decode are skipped, see [`decode_details`].
+pub fn decode(bytes: &[u8]) -> Result<Error> {
+    let envelope: Envelope = serde_json::from_slice(bytes)
+        .map_err(|err| Error::invalid_argument("Invalid JSON error envelope").with_error(err))?;
+    let error = envelope.error;
+    let code = match &error.status {
+        Some(status) => Code::from_str(status).map_err(|err| {
+            Error::invalid_argument(format!("Unknown status in JSON error envelope: {status}"))
+                .with_error(err)
+        })?,
+        None => http::StatusCode::from_u16(error.code)
+            .map(Code::from_http_status)
+            .map_err(|err| {
+                Error::invalid_argument("Invalid code in JSON error envelope").with_error(err)
+            })?,
+    };
+    let details = decode_details(error.details);
+    Ok(Error::new(
+        code,
+        ErrorStatus {
+            message: (!error.message.is_empty()).then_some(error.message.into()),
+            details: (!details.is_empty()).then_some(details),
+            source: None,
+            backtrace: None,
+        },
+    ))
+}
+
+/// Decodes the `google.rpc.Status` JSON form of each of the `details`,
+/// skipping any that fails to decode and keeping the rest, as
+/// [`proto::decode_status`](crate::proto::decode_status) does.
+pub fn decode_details(details: Vec<Value>) -> Vec<ErrorDetails> {
+    details
+        .into_iter()
+        .filter_map(|details| serde_json::from_value(details).ok())
+        .collect()
+}
+
+impl Error {
+    /// Builds a response with the error as a JSON error envelope body,
+    /// redacted by the default [`Redactor`].
+    pub fn into_google_json_response(self) -> http::Response<Vec<u8>> {
+        self.into_google_json_response_with(&Redactor::default())
+    }
+
+    /// Builds a response with the error as a JSON error envelope body,
+    /// redacted by `redactor`.
+    pub fn into_google_json_response_with(self, redactor: &Redactor) -> http::Response<Vec<u8>> {
+        let error = redactor.redact(self);
+        let mut response = http::Response::new(encode(&error));
+        *response.status_mut() = error.http_status();
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static(CONTENT_TYPE),
+        );
+        response
+    }
+
+    /// Parses a response with a JSON error envelope body into an error.
+    pub fn from_google_json_response<B: AsRef<[u8]>>(
+        response: &http::Response<B>,
+    ) -> Result<Error> {
+        decode(response.body().as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn error_into_google_json_response() {
+        let response = Error::not_found("Greeting not found")
+            .with_details(ErrorDetails::resource_info("greeting", "hello"))
+            .into_google_json_response();
+        assert_eq!(response.status(), http::StatusCode::NOT_FOUND);
+        let body: Value = serde_json::from_slice(response.body()).expect("JSON body");
+        assert_eq!(
+            body,
+            json!({
+                "error": {
+                    "code": 404,
+                    "message": "Greeting not found",
+                    "status": "NOT_FOUND",
+                    "details": [
+                        {
+                            "@type": "type.googleapis.com/google.rpc.ResourceInfo",
+                            "resourceType": "greeting",
+                            "resourceName": "hello",
+                        },
+                    ],
+                },
+            })
+        );
+
+        let error = Error::from_google_json_response(&response).expect("decoded envelope");
+        assert_eq!(error.code(), Code::NotFound);
+        assert_eq!(encode(&error), *response.body());
+    }
+
+    #[test]
+    fn decode_google_cloud_error() {
+        let body = json!({
+            "error": {
+                "code": 400,
+                "message": "API key not valid. Please pass a valid API key.",
+                "status": "INVALID_ARGUMENT",
+                "details": [
+                    {
+                        "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                        "reason": "API_KEY_INVALID",
+                        "domain": "googleapis.com",
+                        "metadata": { "service": "translate.googleapis.com" },
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.example.Unknown",
+                        "value": 1,
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.rpc.RetryInfo",
+                        "retryDelay": "soon",
+                    },
+                ],
+            },
+        });
+        let error = decode(&serde_json::to_vec(&body).expect("JSON body")).expect("decoded");
+        assert_eq!(error.code(), Code::InvalidArgument);
+        match error.inner().details.as_deref() {
+            Some(
+                [ErrorDetails::ErrorInfo {
+                    reason, metadata, ..
+                }],
+            ) => {
+                assert_eq!(reason, "API_KEY_INVALID");
+                assert_eq!(
+                    metadata.get("service").map(String::as_str),
+                    Some("translate.googleapis.com")
+                );
+            }
+            other => panic!("unexpected details: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn decode_without_status_uses_http_code() {
+        let error = decode(br#"{"error":{"code":503,"message":"Try later"}}"#).expect("decoded");
+        assert_eq!(error.code(), Code::Unavailable);
+        assert_eq!(error.inner().message.as_deref(), Some("Try later"));
+    }
+}
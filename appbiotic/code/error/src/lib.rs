@@ -10,526 +10,364 @@
 //! descriptions are copied directly from the
 //! https://github.com/googleapis/googleapis project.
 
-use std::fmt;
+#[cfg(feature = "with-http")]
+use std::time::SystemTime;
+use std::{
+    backtrace::Backtrace, borrow::Cow, collections::BTreeMap, error, fmt, str::FromStr, sync::Arc,
+    time::Duration,
+};
 
 use strum_macros::IntoStaticStr;
 
-pub mod code {
-    pub const OK: i32 = 0;
-    pub const CANCELLED: i32 = 1;
-    pub const UNKNOWN: i32 = 2;
-    pub const INVALID_ARGUMENT: i32 = 3;
-    pub const DEADLINE_EXCEEDED: i32 = 4;
-    pub const NOT_FOUND: i32 = 5;
-    pub const ALREADY_EXISTS: i32 = 6;
-    pub const PERMISSION_DENIED: i32 = 7;
-    pub const UNAUTHENTICATED: i32 = 16;
-    pub const RESOURCE_EXHAUSTED: i32 = 8;
-    pub const FAILED_PRECONDITION: i32 = 9;
-    pub const ABORTED: i32 = 10;
-    pub const OUT_OF_RANGE: i32 = 11;
-    pub const UNIMPLEMENTED: i32 = 12;
-    pub const INTERNAL: i32 = 13;
-    pub const UNAVAILABLE: i32 = 14;
-    pub const DATA_LOSS: i32 = 15;
-}
+pub mod backtrace;
+pub mod batch;
+pub mod catalog;
+pub mod code;
+pub mod convert;
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod exit;
+mod ext;
+#[cfg(feature = "with-http-json")]
+pub mod google_json;
+pub mod path;
+#[cfg(feature = "with-http-json")]
+pub mod problem;
+pub mod proto;
+pub mod reason;
+pub mod redact;
+pub mod retry;
+#[cfg(feature = "with-http")]
+mod retry_after;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod validate;
+
+pub use code::Code;
+pub use ext::{OptionExt, ResultExt};
 
 // TODO: Find or create library for format and flow markdown comments.
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Clone, Debug, thiserror::Error, IntoStaticStr)]
-#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
-pub enum Error {
-    /// The operation was cancelled, typically by the caller.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  499 | Client Closed Request                               |
-    /// | gRPC    |    1 | Cancelled                                           |
-    Cancelled(ErrorStatus),
-
-    /// Unknown error. For example, this error may be returned when a [`ErrorStatus`]
-    /// value received from another address space belongs to an error space
-    /// that is not known in this address space. Also errors raised by APIs
-    /// that do not return enough error information may be converted to this
-    /// error.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  500 | Internal Server Error                               |
-    /// | gRPC    |    2 | Unknown                                             |
-    Unknown(ErrorStatus),
-
-    /// The client specified an invalid argument.  Note that this differs
-    /// from [`Error::FailedPrecondition`].  [`Error::InvalidArgument`] indicates arguments
-    /// that are problematic regardless of the state of the system
-    /// (e.g., a malformed file name).
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  400 | Bad Request                                         |
-    /// | gRPC    |    3 | Invalid argument                                    |
-    InvalidArgument(ErrorStatus),
-
-    /// The deadline expired before the operation could complete. For operations
-    /// that change the state of the system, this error may be returned
-    /// even if the operation has completed successfully.  For example, a
-    /// successful response from a server could have been delayed long
-    /// enough for the deadline to expire.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  504 | Gateway Timeout                                     |
-    /// | gRPC    |    4 | Deadline exceeded                                   |
-    DeadlineExceeded(ErrorStatus),
-
-    /// Some requested entity (e.g., file or directory) was not found.
-    ///
-    /// Note to server developers: if a request is denied for an entire class
-    /// of users, such as gradual feature rollout or undocumented allowlist,
-    /// [`Error::NotFound`] may be used. If a request is denied for some users
-    /// within a class of users, such as user-based access control,
-    /// [`Error::PermissionDenied`] must be used.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  404 | Not Found                                           |
-    /// | gRPC    |    5 | Not found                                           |
-    NotFound(ErrorStatus),
-
-    /// The entity that a client attempted to create (e.g., file or directory)
-    /// already exists.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  409 | Conflict                                            |
-    /// | gRPC    |    6 | Already exists                                      |
-    AlreadyExists(ErrorStatus),
-
-    /// The caller does not have permission to execute the specified
-    /// operation. [`Error::PermissionDenied`] must not be used for rejections
-    /// caused by exhausting some resource (use [`Error::ResourceExhausted`]
-    /// instead for those errors). [`Error::PermissionDenied`] must not be
-    /// used if the caller can not be identified (use [`Error::Unauthenticated`]
-    /// instead for those errors). This error code does not imply the
-    /// request is valid or the requested entity exists or satisfies
-    /// other pre-conditions.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  403 | Forbidden                                           |
-    /// | gRPC    |    7 | Permission denied                                   |
-    PermissionDenied(ErrorStatus),
-
-    /// The request does not have valid authentication credentials for the
-    /// operation.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  401 | Unauthorized                                        |
-    /// | gRPC    |   16 | Permission denied                                   |
-    Unauthenticated(ErrorStatus),
-
-    /// Some resource has been exhausted, perhaps a per-user quota, or
-    /// perhaps the entire file system is out of space.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  429 | Too Many Requests                                   |
-    /// | gRPC    |    8 | Permission denied                                   |
-    ResourceExhausted(ErrorStatus),
-
-    /// The operation was rejected because the system is not in a state
-    /// required for the operation's execution.  For example, the directory
-    /// to be deleted is non-empty, an rmdir operation is applied to
-    /// a non-directory, etc.
-    ///
-    /// Service implementors can use the following guidelines to decide
-    /// between [`Error::FailedPrecondition`], [`Error::Aborted`], and
-    /// [`Error::Unavailable`]:
-    ///
-    ///  - Use [`Error::Unavailable`] if the client can retry just the failing
-    ///    call.
-    ///  - Use [`Error::Aborted`] if the client should retry at a higher level.
-    ///    For example, when a client-specified test-and-set fails, indicating
-    ///    the client should restart a read-modify-write sequence.
-    ///  - Use [`Error::FailedPrecondition`] if the client should not retry
-    ///    until the system state has been explicitly fixed. For example, if an
-    ///    "rmdir" fails because the directory is non-empty,
-    ///    [`Error::FailedPrecondition`] should be returned since the client
-    ///    should not retry unless the files are deleted from the directory.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  400 | Bad Request                                         |
-    /// | gRPC    |    9 | Failed precondition                                 |
-    FailedPrecondition(ErrorStatus),
-
-    /// The operation was aborted, typically due to a concurrency issue such as
-    /// a sequencer check failure or transaction abort.
-    ///
-    /// See the guidelines above for deciding between
-    /// [`Error::FailedPrecondition`], [`Error::Aborted`], and
-    /// [`Error::Unavailable`].
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  409 | Conflict                                            |
-    /// | gRPC    |   10 | Aborted                                             |
-    Aborted(ErrorStatus),
-
-    /// The operation was attempted past the valid range.  E.g., seeking or
-    /// reading past end-of-file.
-    ///
-    /// Unlike [`Error::InvalidArgument`], this error indicates a problem that
-    /// may be fixed if the system state changes. For example, a 32-bit file
-    /// system will generate [`Error::InvalidArgument`] if asked to read at an
-    /// offset that is not in the range [0,2^32-1], but it will generate
-    /// [`Error::OutOfRange`] if asked to read from an offset past the current
-    /// file size.
-    ///
-    /// There is a fair bit of overlap between [`Error::FailedPrecondition`] and
-    /// [`Error::OutOfRange`].  We recommend using [`Error::OutOfRange`] (the
-    /// more specific error) when it applies so that callers who are iterating
-    /// through a space can easily look for an [`Error::OutOfRange`] error to
-    /// detect when they are done.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  400 | Bad Request                                         |
-    /// | gRPC    |   11 | Out of range                                        |
-    OutOfRange(ErrorStatus),
-
-    /// The operation is not implemented or is not supported/enabled in this
-    /// service.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  501 | Not implemented                                     |
-    /// | gRPC    |   12 | Unimplemented                                       |
-    Unimplemented(ErrorStatus),
-
-    /// Internal errors.  This means that some invariants expected by the
-    /// underlying system have been broken.  This error code is reserved for
-    /// serious errors.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  500 | Internal Server Error                               |
-    /// | gRPC    |   13 | Internal                                            |
-    Internal(ErrorStatus),
-
-    /// The service is currently unavailable.  This is most likely a transient
-    /// condition, which can be corrected by retrying with
-    /// a backoff. Note that it is not always safe to retry
-    /// non-idempotent operations.
-    ///
-    /// See the guidelines above for deciding between
-    /// [`Error::FailedPrecondition`], [`Error::Aborted`], and
-    /// [`Error::Unavailable`].
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  503 | Service Unavailable                                 |
-    /// | gRPC    |   14 | Unavailable                                         |
-    Unavailable(ErrorStatus),
-
-    /// Unrecoverable data loss or corruption.
-    ///
-    /// | Mapping | Code | Description                                         |
-    /// | :------ | ---: | :-------------------------------------------------- |
-    /// | HTTP    |  500 | Internal Server Error                               |
-    /// | gRPC    |   15 | Data loss                                           |
-    DataLoss(ErrorStatus),
+/// An error with one of the canonical [`Code`]s and an [`ErrorStatus`]
+/// carrying the message and details.
+///
+/// The status is boxed so that `Result<T, Error>` stays small on the hot path
+/// where no error occurs.
+#[derive(Clone, Debug)]
+pub struct Error {
+    code: Code,
+    status: Box<ErrorStatus>,
 }
 
-// TODO: Replace strum with a more detailed display implementation.
+/// Renders the code name, message and a summary of each of the details, e.g.,
+/// `INVALID_ARGUMENT: Invalid greeting (bad request: name: Too long)`.
+///
+/// The alternate form, `{:#}`, renders each of the details on its own
+/// indented lines for terminals and logs.
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.into())
+        f.write_str(self.code.as_str())?;
+        let status = self.inner();
+        if let Some(message) = &status.message {
+            write!(f, ": {message}")?;
+        }
+        let details = status.details.as_deref().unwrap_or_default();
+        if f.alternate() {
+            let backtrace = status
+                .stack_entries()
+                .map(|stack_entries| ErrorDetails::DebugInfo {
+                    stack_entries: Some(stack_entries),
+                    detail: Some("backtrace".to_owned()),
+                });
+            for details in details.iter().chain(&backtrace) {
+                for line in format!("{details:#}").lines() {
+                    write!(f, "\n  {line}")?;
+                }
+            }
+        } else if !details.is_empty() {
+            f.write_str(" (")?;
+            for (i, details) in details.iter().enumerate() {
+                if i > 0 {
+                    f.write_str("; ")?;
+                }
+                write!(f, "{details}")?;
+            }
+            f.write_str(")")?;
+        }
+        Ok(())
     }
 }
 
-impl Error {
-    pub fn inner(&self) -> &ErrorStatus {
-        match self {
-            Error::Internal(status) => status,
-            Error::Unknown(status) => status,
-            Error::Cancelled(status) => status,
-            Error::InvalidArgument(status) => status,
-            Error::DeadlineExceeded(status) => status,
-            Error::NotFound(status) => status,
-            Error::AlreadyExists(status) => status,
-            Error::PermissionDenied(status) => status,
-            Error::Unauthenticated(status) => status,
-            Error::ResourceExhausted(status) => status,
-            Error::FailedPrecondition(status) => status,
-            Error::Aborted(status) => status,
-            Error::OutOfRange(status) => status,
-            Error::Unimplemented(status) => status,
-            Error::Unavailable(status) => status,
-            Error::DataLoss(status) => status,
-        }
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.inner()
+            .source()
+            .map(|source| source as &(dyn error::Error + 'static))
     }
 }
 
 impl From<Error> for ErrorStatus {
     fn from(value: Error) -> Self {
-        match value {
-            Error::Internal(status) => status,
-            Error::Unknown(status) => status,
-            Error::Cancelled(status) => status,
-            Error::InvalidArgument(status) => status,
-            Error::DeadlineExceeded(status) => status,
-            Error::NotFound(status) => status,
-            Error::AlreadyExists(status) => status,
-            Error::PermissionDenied(status) => status,
-            Error::Unauthenticated(status) => status,
-            Error::ResourceExhausted(status) => status,
-            Error::FailedPrecondition(status) => status,
-            Error::Aborted(status) => status,
-            Error::OutOfRange(status) => status,
-            Error::Unimplemented(status) => status,
-            Error::Unavailable(status) => status,
-            Error::DataLoss(status) => status,
-        }
+        *value.status
     }
 }
 
 impl Error {
-    /// Returns the gRPC code value.
+    pub fn new(code: Code, status: ErrorStatus) -> Error {
+        Error {
+            code,
+            status: Box::new(status),
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    pub fn inner(&self) -> &ErrorStatus {
+        &self.status
+    }
+
+    pub fn inner_mut(&mut self) -> &mut ErrorStatus {
+        &mut self.status
+    }
+
+    /// Builds the error matching the gRPC `code` value with the given `status`.
     ///
-    /// See https://github.com/googleapis/googleapis/blob/f36c65081b19e0758ef5696feca27c7dcee5475e/google/rpc/code.proto.
-    pub fn code(&self) -> i32 {
-        match self {
-            Error::Cancelled(_) => code::CANCELLED,
-            Error::Unknown(_) => code::UNKNOWN,
-            Error::InvalidArgument(_) => code::INVALID_ARGUMENT,
-            Error::DeadlineExceeded(_) => code::DEADLINE_EXCEEDED,
-            Error::NotFound(_) => code::NOT_FOUND,
-            Error::AlreadyExists(_) => code::ALREADY_EXISTS,
-            Error::PermissionDenied(_) => code::PERMISSION_DENIED,
-            Error::Unauthenticated(_) => code::UNAUTHENTICATED,
-            Error::ResourceExhausted(_) => code::RESOURCE_EXHAUSTED,
-            Error::FailedPrecondition(_) => code::FAILED_PRECONDITION,
-            Error::Aborted(_) => code::ABORTED,
-            Error::OutOfRange(_) => code::OUT_OF_RANGE,
-            Error::Unimplemented(_) => code::UNIMPLEMENTED,
-            Error::Internal(_) => code::INTERNAL,
-            Error::Unavailable(_) => code::UNAVAILABLE,
-            Error::DataLoss(_) => code::DATA_LOSS,
-        }
-    }
-
-    // TODO: Build macros to automate building of the error helper functions.
+    /// Fails with [`Code::InvalidArgument`] for [`code::OK`] or any value
+    /// outside of the known codes.
+    pub fn from_code(code: i32, status: ErrorStatus) -> Result<Error> {
+        Ok(Error::new(Code::try_from(code)?, status))
+    }
+
+    /// Creates an error with `code` and `message` and a backtrace of the
+    /// caller if capturing backtraces is enabled.
+    ///
+    /// Unlike the constructors of each code, e.g., [`Error::not_found`], which
+    /// take any `AsRef<str>`, a constant message is borrowed rather than
+    /// allocated.
+    pub fn with_code<S: Into<Cow<'static, str>>>(code: Code, message: S) -> Error {
+        Error::new(
+            code,
+            ErrorStatus {
+                message: Some(message.into()),
+                ..ErrorStatus::captured()
+            },
+        )
+    }
 
     pub fn cancelled<S: AsRef<str>>(message: S) -> Error {
-        Error::Cancelled(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::Cancelled, message.as_ref().to_owned())
     }
 
     pub fn unknown<S: AsRef<str>>(message: S) -> Error {
-        Error::Unknown(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::Unknown, message.as_ref().to_owned())
     }
 
     pub fn invalid_argument<S: AsRef<str>>(message: S) -> Error {
-        Error::InvalidArgument(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::InvalidArgument, message.as_ref().to_owned())
     }
 
     pub fn deadline_exceeded<S: AsRef<str>>(message: S) -> Error {
-        Error::DeadlineExceeded(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::DeadlineExceeded, message.as_ref().to_owned())
     }
 
     pub fn not_found<S: AsRef<str>>(message: S) -> Error {
-        Error::NotFound(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::NotFound, message.as_ref().to_owned())
     }
 
     pub fn already_exists<S: AsRef<str>>(message: S) -> Error {
-        Error::AlreadyExists(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::AlreadyExists, message.as_ref().to_owned())
     }
 
     pub fn permission_denied<S: AsRef<str>>(message: S) -> Error {
-        Error::PermissionDenied(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::PermissionDenied, message.as_ref().to_owned())
     }
 
     pub fn unauthenticated<S: AsRef<str>>(message: S) -> Error {
-        Error::Unauthenticated(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::Unauthenticated, message.as_ref().to_owned())
     }
 
     pub fn resource_exhausted<S: AsRef<str>>(message: S) -> Error {
-        Error::ResourceExhausted(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::ResourceExhausted, message.as_ref().to_owned())
     }
 
     pub fn failed_precondition<S: AsRef<str>>(message: S) -> Error {
-        Error::FailedPrecondition(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::FailedPrecondition, message.as_ref().to_owned())
     }
 
     pub fn aborted<S: AsRef<str>>(message: S) -> Error {
-        Error::Aborted(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::Aborted, message.as_ref().to_owned())
     }
 
     pub fn out_of_range<S: AsRef<str>>(message: S) -> Error {
-        Error::OutOfRange(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::OutOfRange, message.as_ref().to_owned())
     }
 
     pub fn unimplemented<S: AsRef<str>>(message: S) -> Error {
-        Error::Unimplemented(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::Unimplemented, message.as_ref().to_owned())
     }
 
     pub fn internal<S: AsRef<str>>(message: S) -> Error {
-        Error::Internal(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::Internal, message.as_ref().to_owned())
     }
 
     pub fn unavailable<S: AsRef<str>>(message: S) -> Error {
-        Error::Unavailable(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::Unavailable, message.as_ref().to_owned())
     }
 
     pub fn data_loss<S: AsRef<str>>(message: S) -> Error {
-        Error::DataLoss(ErrorStatus::default().with_message(message))
+        Error::with_code(Code::DataLoss, message.as_ref().to_owned())
     }
 
-    /// Appends a `ErrorDetails::DebugInfo` with info from `error`.
-    pub fn with_error<E: fmt::Display>(self, error: E) -> Error {
-        match self {
-            Error::Cancelled(details) => Error::Cancelled(details.with_error(error)),
-            Error::Unknown(details) => Error::Unknown(details.with_error(error)),
-            Error::InvalidArgument(details) => Error::InvalidArgument(details.with_error(error)),
-            Error::DeadlineExceeded(details) => Error::DeadlineExceeded(details.with_error(error)),
-            Error::NotFound(details) => Error::NotFound(details.with_error(error)),
-            Error::AlreadyExists(details) => Error::AlreadyExists(details.with_error(error)),
-            Error::PermissionDenied(details) => Error::PermissionDenied(details.with_error(error)),
-            Error::Unauthenticated(details) => Error::Unauthenticated(details.with_error(error)),
-            Error::ResourceExhausted(details) => {
-                Error::ResourceExhausted(details.with_error(error))
-            }
-            Error::FailedPrecondition(details) => {
-                Error::FailedPrecondition(details.with_error(error))
+    /// Adds `error` as the cause of the error, see [`ErrorStatus::with_error`].
+    pub fn with_error<E: Into<BoxError>>(self, error: E) -> Error {
+        self.map_inner(|status| status.with_error(error))
+    }
+
+    /// Returns the first error of type `E` in the chain of causes, e.g., the
+    /// `std::io::Error` that a retry decision depends on.
+    pub fn find_source<E: error::Error + 'static>(&self) -> Option<&E> {
+        let mut source = error::Error::source(self);
+        while let Some(error) = source {
+            let found = match error.downcast_ref::<ChainedError>() {
+                Some(chained) => chained.error.downcast_ref::<E>(),
+                None => error.downcast_ref::<E>(),
+            };
+            if found.is_some() {
+                return found;
             }
-            Error::Aborted(details) => Error::Aborted(details.with_error(error)),
-            Error::OutOfRange(details) => Error::OutOfRange(details.with_error(error)),
-            Error::Unimplemented(details) => Error::Unimplemented(details.with_error(error)),
-            Error::Internal(details) => Error::Internal(details.with_error(error)),
-            Error::Unavailable(details) => Error::Unavailable(details.with_error(error)),
-            Error::DataLoss(details) => Error::DataLoss(details.with_error(error)),
+            source = error.source();
         }
+        None
+    }
+
+    /// Sends the captured backtrace to clients, see
+    /// [`ErrorStatus::with_stack_entries`].
+    pub fn with_stack_entries(self) -> Error {
+        self.map_inner(ErrorStatus::with_stack_entries)
+    }
+
+    /// Appends `details` to the error's status.
+    pub fn with_details(self, details: ErrorDetails) -> Error {
+        self.map_inner(|status| status.with_details(details))
+    }
+
+    fn map_inner<F: FnOnce(ErrorStatus) -> ErrorStatus>(mut self, f: F) -> Error {
+        *self.status = f(std::mem::take(&mut *self.status));
+        self
+    }
+}
+
+#[cfg(feature = "with-http")]
+impl Error {
+    /// Returns the HTTP status code from the mapping table of the code, see
+    /// [`Code::http_status`].
+    pub fn http_status(&self) -> http::StatusCode {
+        self.code.http_status()
     }
 }
 
 #[cfg(feature = "with-http")]
 impl From<Error> for http::StatusCode {
     fn from(value: Error) -> Self {
-        match value {
-            Error::Cancelled(_) => {
-                http::StatusCode::from_u16(499).unwrap_or(http::StatusCode::IM_A_TEAPOT)
-            }
-            Error::Unknown(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
-            Error::InvalidArgument(_) => http::StatusCode::BAD_REQUEST,
-            Error::DeadlineExceeded(_) => http::StatusCode::GATEWAY_TIMEOUT,
-            Error::NotFound(_) => http::StatusCode::NOT_FOUND,
-            Error::AlreadyExists(_) => http::StatusCode::CONFLICT,
-            Error::PermissionDenied(_) => http::StatusCode::FORBIDDEN,
-            Error::Unauthenticated(_) => http::StatusCode::UNAUTHORIZED,
-            Error::ResourceExhausted(_) => http::StatusCode::TOO_MANY_REQUESTS,
-            Error::FailedPrecondition(_) => http::StatusCode::BAD_REQUEST,
-            Error::Aborted(_) => http::StatusCode::CONFLICT,
-            Error::OutOfRange(_) => http::StatusCode::BAD_REQUEST,
-            Error::Unimplemented(_) => http::StatusCode::NOT_IMPLEMENTED,
-            Error::Internal(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
-            Error::Unavailable(_) => http::StatusCode::SERVICE_UNAVAILABLE,
-            Error::DataLoss(_) => http::StatusCode::INTERNAL_SERVER_ERROR,
-        }
-    }
-}
-
-// TODO: Properly map error details into a tonic Status.
+        value.http_status()
+    }
+}
+
+#[cfg(feature = "with-http")]
+impl Error {
+    /// Builds the error for an HTTP error `status` received from another
+    /// service, with the code mapped by [`Code::from_http_status`].
+    pub fn from_http_status(status: http::StatusCode) -> Error {
+        Error::new(
+            Code::from_http_status(status),
+            ErrorStatus::default().with_message(format!("HTTP {status}")),
+        )
+    }
+
+    /// Builds the error for an HTTP error response received from another
+    /// service, as [`Error::from_http_status`] does.
+    ///
+    /// A `Retry-After` header is added as a `ErrorDetails::RetryInfo` and a
+    /// non-empty `body` is kept as a `ErrorDetails::DebugInfo`.
+    pub fn from_http_response(
+        status: http::StatusCode,
+        headers: &http::HeaderMap,
+        body: &[u8],
+    ) -> Error {
+        let mut error = Error::from_http_status(status);
+        let retry_delay = headers
+            .get(http::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| retry_after::parse(value, SystemTime::now()));
+        if let Some(retry_delay) = retry_delay {
+            error = error.with_details(ErrorDetails::retry_info(retry_delay));
+        }
+        if !body.is_empty() {
+            error = error.with_details(ErrorDetails::debug_info(String::from_utf8_lossy(body)));
+        }
+        error
+    }
+}
+
 #[cfg(feature = "with-tonic")]
 impl Error {
+    /// Converts into a [`tonic::Status`] with the same code and message,
+    /// redacted by the default [`Redactor`](redact::Redactor).
+    ///
+    /// Any details are encoded as a `google.rpc.Status` message in the
+    /// `grpc-status-details-bin` trailer.
     pub fn into_tonic_status(self) -> tonic::Status {
-        match self {
-            Error::Internal(status) => {
-                tonic::Status::new(tonic::Code::Internal, status.message.unwrap_or_default())
-            }
-            Error::Cancelled(status) => {
-                tonic::Status::new(tonic::Code::Cancelled, status.message.unwrap_or_default())
-            }
-            Error::Unknown(status) => {
-                tonic::Status::new(tonic::Code::Unknown, status.message.unwrap_or_default())
-            }
-            Error::InvalidArgument(status) => tonic::Status::new(
-                tonic::Code::InvalidArgument,
-                status.message.unwrap_or_default(),
-            ),
-            Error::DeadlineExceeded(status) => tonic::Status::new(
-                tonic::Code::DeadlineExceeded,
-                status.message.unwrap_or_default(),
-            ),
-            Error::NotFound(status) => {
-                tonic::Status::new(tonic::Code::NotFound, status.message.unwrap_or_default())
-            }
-            Error::AlreadyExists(status) => tonic::Status::new(
-                tonic::Code::AlreadyExists,
-                status.message.unwrap_or_default(),
-            ),
-            Error::PermissionDenied(status) => tonic::Status::new(
-                tonic::Code::PermissionDenied,
-                status.message.unwrap_or_default(),
-            ),
-            Error::Unauthenticated(status) => tonic::Status::new(
-                tonic::Code::Unauthenticated,
-                status.message.unwrap_or_default(),
-            ),
-            Error::ResourceExhausted(status) => tonic::Status::new(
-                tonic::Code::ResourceExhausted,
-                status.message.unwrap_or_default(),
-            ),
-            Error::FailedPrecondition(status) => tonic::Status::new(
-                tonic::Code::FailedPrecondition,
-                status.message.unwrap_or_default(),
-            ),
-            Error::Aborted(status) => {
-                tonic::Status::new(tonic::Code::Aborted, status.message.unwrap_or_default())
-            }
-            Error::OutOfRange(status) => {
-                tonic::Status::new(tonic::Code::OutOfRange, status.message.unwrap_or_default())
-            }
-            Error::Unimplemented(status) => tonic::Status::new(
-                tonic::Code::Unimplemented,
-                status.message.unwrap_or_default(),
-            ),
-            Error::Unavailable(status) => {
-                tonic::Status::new(tonic::Code::Unavailable, status.message.unwrap_or_default())
-            }
-            Error::DataLoss(status) => {
-                tonic::Status::new(tonic::Code::DataLoss, status.message.unwrap_or_default())
-            }
+        self.into_tonic_status_with(&redact::Redactor::default())
+    }
+
+    /// Converts into a [`tonic::Status`] like [`Error::into_tonic_status`],
+    /// redacted by `redactor`.
+    pub fn into_tonic_status_with(self, redactor: &redact::Redactor) -> tonic::Status {
+        let error = redactor.redact(self);
+        let code = tonic::Code::from(error.code);
+        let message = error
+            .inner()
+            .message
+            .as_deref()
+            .unwrap_or_default()
+            .to_owned();
+        let status = error.inner();
+        if status.details.is_some() || status.source.is_some() {
+            let details = bytes::Bytes::from(proto::encode_status(&error));
+            tonic::Status::with_details(code, message, details)
+        } else {
+            tonic::Status::new(code, message)
         }
     }
 }
 
+#[cfg(feature = "with-tonic")]
+impl From<Error> for tonic::Status {
+    fn from(value: Error) -> Self {
+        value.into_tonic_status()
+    }
+}
+
 #[cfg(feature = "with-tonic")]
 impl TryFrom<tonic::Status> for Error {
     type Error = Error;
 
-    fn try_from(value: tonic::Status) -> Result<Self, Self::Error> {
-        match value.code() {
-            tonic::Code::Ok => Err(Error::invalid_argument("Cannot convert OK status to Error")),
-            tonic::Code::Cancelled => Ok(Error::cancelled(value.message())),
-            tonic::Code::Unknown => Ok(Error::unknown(value.message())),
-            tonic::Code::InvalidArgument => Ok(Error::invalid_argument(value.message())),
-            tonic::Code::DeadlineExceeded => Ok(Error::deadline_exceeded(value.message())),
-            tonic::Code::NotFound => Ok(Error::not_found(value.message())),
-            tonic::Code::AlreadyExists => Ok(Error::already_exists(value.message())),
-            tonic::Code::PermissionDenied => Ok(Error::permission_denied(value.message())),
-            tonic::Code::ResourceExhausted => Ok(Error::resource_exhausted(value.message())),
-            tonic::Code::FailedPrecondition => Ok(Error::failed_precondition(value.message())),
-            tonic::Code::Aborted => Ok(Error::aborted(value.message())),
-            tonic::Code::OutOfRange => Ok(Error::out_of_range(value.message())),
-            tonic::Code::Unimplemented => Ok(Error::unimplemented(value.message())),
-            tonic::Code::Internal => Ok(Error::internal(value.message())),
-            tonic::Code::Unavailable => Ok(Error::unavailable(value.message())),
-            tonic::Code::DataLoss => Ok(Error::data_loss(value.message())),
-            tonic::Code::Unauthenticated => Ok(Error::unauthenticated(value.message())),
+    /// Converts from a [`tonic::Status`], decoding any details found in the
+    /// `grpc-status-details-bin` trailer. The code and message of the status
+    /// are kept even if the trailer is malformed, in which case its details
+    /// are dropped.
+    fn try_from(value: tonic::Status) -> std::result::Result<Self, Self::Error> {
+        let mut status = ErrorStatus::default().with_message(value.message());
+        if !value.details().is_empty() {
+            status.details = proto::decode_status(value.details())
+                .ok()
+                .and_then(|error| ErrorStatus::from(error).details);
         }
+        Ok(Error::new(Code::try_from(value.code())?, status))
     }
 }
 
@@ -545,31 +383,211 @@ pub struct ErrorStatus {
     /// A developer-facing error message, which should be in English. Any
     /// user-facing error message should be localized and sent in the
     /// `details` field in a `ErrorDetails::LocalizedMessage`.
-    pub message: Option<String>,
+    ///
+    /// Constant messages are borrowed rather than allocated.
+    pub message: Option<Cow<'static, str>>,
     /// A list of messages that carry the error details.  There is a common set
     /// of message types for APIs to use.    
     pub details: Option<Vec<ErrorDetails>>,
+    /// The underlying cause of the error, which is not sent to clients as is
+    /// but rendered into a `ErrorDetails::DebugInfo` when the status is
+    /// encoded, see [`ErrorStatus::encoded_details`].
+    pub source: Option<Arc<dyn error::Error + Send + Sync>>,
+    /// The backtrace of where the error was created, rendered by the alternate
+    /// `Display` of an [`Error`] but not sent to clients unless added with
+    /// [`ErrorStatus::with_stack_entries`], see [`backtrace`].
+    pub backtrace: Option<Arc<Backtrace>>,
+}
+
+/// A type-erased error that can be kept as the cause of an [`ErrorStatus`].
+pub type BoxError = Box<dyn error::Error + Send + Sync>;
+
+/// A cause added by [`ErrorStatus::with_error`] in front of a previous one.
+#[derive(Debug)]
+struct ChainedError {
+    error: BoxError,
+    previous: Arc<dyn error::Error + Send + Sync>,
+}
+
+impl fmt::Display for ChainedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        let mut cause = self.error.source();
+        while let Some(error) = cause {
+            write!(f, ": {error}")?;
+            cause = error.source();
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ChainedError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&*self.previous)
+    }
 }
 
 impl ErrorStatus {
+    /// Creates an empty status with a backtrace of the caller if capturing
+    /// backtraces is enabled, see [`backtrace::set_capture`].
+    pub fn captured() -> Self {
+        ErrorStatus {
+            backtrace: backtrace::capture_backtrace(),
+            ..ErrorStatus::default()
+        }
+    }
+
     pub fn with_message<M: AsRef<str>>(self, message: M) -> Self {
         ErrorStatus {
-            message: Some(message.as_ref().to_owned()),
-            details: self.details,
+            message: Some(message.as_ref().to_owned().into()),
+            ..self
         }
     }
 
-    pub fn with_error<E: fmt::Display>(self, error: E) -> Self {
-        let mut details = self.details.unwrap_or_default();
-        details.push(ErrorDetails::DebugInfo {
-            stack_entries: None,
-            detail: Some(error.to_string()),
+    /// Sets `error` as the cause. Plain messages are accepted too, e.g.,
+    /// `with_error("Disk full")`.
+    ///
+    /// A previous cause is kept as the source of `error`, so that
+    /// `with_error(io_error).with_error("Reading greetings")` renders the
+    /// chain `Reading greetings: <io_error>`. The chain of `error` itself is
+    /// then rendered with its message.
+    pub fn with_error<E: Into<BoxError>>(self, error: E) -> Self {
+        let error = error.into();
+        let source: Arc<dyn error::Error + Send + Sync> = match self.source {
+            Some(previous) => Arc::new(ChainedError { error, previous }),
+            None => Arc::from(error),
+        };
+        ErrorStatus {
+            source: Some(source),
+            ..self
+        }
+    }
+
+    /// Sets the backtrace of where the error was created.
+    pub fn with_backtrace(self, backtrace: Backtrace) -> Self {
+        ErrorStatus {
+            backtrace: Some(Arc::new(backtrace)),
+            ..self
+        }
+    }
+
+    /// Returns the resolved frames of the backtrace, if one was captured.
+    pub fn stack_entries(&self) -> Option<Vec<String>> {
+        self.backtrace
+            .as_deref()
+            .map(backtrace::stack_entries)
+            .filter(|stack_entries| !stack_entries.is_empty())
+    }
+
+    /// Returns the underlying cause of the error.
+    pub fn source(&self) -> Option<&(dyn error::Error + Send + Sync + 'static)> {
+        self.source.as_deref()
+    }
+
+    /// Moves the frames of the captured backtrace, if any, into the
+    /// `stack_entries` of an appended `ErrorDetails::DebugInfo` so that they
+    /// are sent to clients, e.g., by internal services.
+    pub fn with_stack_entries(self) -> Self {
+        match self.stack_entries() {
+            Some(stack_entries) => ErrorStatus {
+                backtrace: None,
+                ..self
+            }
+            .with_details(ErrorDetails::DebugInfo {
+                stack_entries: Some(stack_entries),
+                detail: Some("backtrace".to_owned()),
+            }),
+            None => self,
+        }
+    }
+
+    /// Returns the details as they are sent to clients: the details followed
+    /// by a `ErrorDetails::DebugInfo` rendering the chain of causes, if any.
+    ///
+    /// The captured backtrace is left out unless it was added to the details
+    /// with [`ErrorStatus::with_stack_entries`].
+    pub fn encoded_details(&self) -> Vec<ErrorDetails> {
+        let mut details = self.details.clone().unwrap_or_default();
+        let detail = self.source().map(|source| {
+            let mut causes = vec![source.to_string()];
+            let mut cause = source.source();
+            while let Some(error) = cause {
+                causes.push(error.to_string());
+                cause = error.source();
+            }
+            causes.join(": ")
         });
+        if detail.is_some() {
+            details.push(ErrorDetails::DebugInfo {
+                stack_entries: None,
+                detail,
+            });
+        }
+        details
+    }
+
+    /// Appends `details` to the list of error details.
+    pub fn with_details(self, details: ErrorDetails) -> Self {
+        let mut all_details = self.details.unwrap_or_default();
+        all_details.push(details);
         ErrorStatus {
-            message: self.message,
-            details: Some(details),
+            details: Some(all_details),
+            ..self
         }
     }
+
+    /// Appends a `ErrorDetails::BadRequest` with a single `field_violation`.
+    pub fn with_field_violation(self, field_violation: FieldViolation) -> Self {
+        self.with_details(ErrorDetails::bad_request(field_violation))
+    }
+
+    /// Appends a `ErrorDetails::LocalizedMessage`.
+    pub fn with_localized_message<L: AsRef<str>, M: AsRef<str>>(
+        self,
+        locale: L,
+        message: M,
+    ) -> Self {
+        self.with_details(ErrorDetails::localized_message(locale, message))
+    }
+
+    /// Appends a `ErrorDetails::ErrorInfo` without metadata.
+    pub fn with_error_info<R: AsRef<str>, D: AsRef<str>>(self, reason: R, domain: D) -> Self {
+        self.with_details(ErrorDetails::error_info(reason, domain))
+    }
+
+    /// Appends a `ErrorDetails::RetryInfo` with the given `retry_delay`.
+    pub fn with_retry_info(self, retry_delay: Duration) -> Self {
+        self.with_details(ErrorDetails::retry_info(retry_delay))
+    }
+
+    /// Appends a `ErrorDetails::QuotaFailure` with a single `violation`.
+    pub fn with_quota_failure(self, violation: QuotaViolation) -> Self {
+        self.with_details(ErrorDetails::quota_failure(violation))
+    }
+
+    /// Appends a `ErrorDetails::PreconditionFailure` with a single `violation`.
+    pub fn with_precondition_failure(self, violation: PreconditionViolation) -> Self {
+        self.with_details(ErrorDetails::precondition_failure(violation))
+    }
+
+    /// Appends a `ErrorDetails::ResourceInfo` without an owner or description.
+    pub fn with_resource_info<T: AsRef<str>, N: AsRef<str>>(
+        self,
+        resource_type: T,
+        resource_name: N,
+    ) -> Self {
+        self.with_details(ErrorDetails::resource_info(resource_type, resource_name))
+    }
+
+    /// Appends a `ErrorDetails::RequestInfo` without serving data.
+    pub fn with_request_info<R: AsRef<str>>(self, request_id: R) -> Self {
+        self.with_details(ErrorDetails::request_info(request_id))
+    }
+
+    /// Appends a `ErrorDetails::Help` with a single `link`.
+    pub fn with_help(self, link: Link) -> Self {
+        self.with_details(ErrorDetails::help(link))
+    }
 }
 
 /// The specific details of an error that may be optionally forwarded to an
@@ -580,12 +598,131 @@ impl ErrorStatus {
 #[derive(Clone, Debug, IntoStaticStr)]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 pub enum ErrorDetails {
+    /// Describes the cause of the error with structured details.
+    ///
+    /// Example of an error when contacting the "pubsub.googleapis.com" API when it
+    /// is not enabled:
+    ///
+    /// ```json
+    ///     { "reason": "API_DISABLED"
+    ///       "domain": "googleapis.com"
+    ///       "metadata": {
+    ///         "resource": "projects/123",
+    ///         "service": "pubsub.googleapis.com"
+    ///       }
+    ///     }
+    /// ```
+    ///
+    /// This response indicates that the pubsub.googleapis.com API is not enabled.
+    ErrorInfo {
+        /// The reason of the error. This is a constant value that identifies the
+        /// proximate cause of the error. Error reasons are unique within a particular
+        /// domain of errors. This should be at most 63 characters and match a
+        /// regular expression of `[A-Z][A-Z0-9_]+[A-Z0-9]`, which represents
+        /// UPPER_SNAKE_CASE.
+        reason: String,
+        /// The logical grouping to which the "reason" belongs. The error domain
+        /// is typically the registered service name of the tool or product that
+        /// generates the error. Example: "pubsub.googleapis.com". If the error is
+        /// generated by some common infrastructure, the error domain must be a
+        /// globally unique value that identifies the infrastructure. For Google API
+        /// infrastructure, the error domain is "googleapis.com".
+        domain: String,
+        /// Additional structured details about this error.
+        ///
+        /// Keys should match /[a-zA-Z0-9-_]/ and be limited to 64 characters in
+        /// length. When identifying the current value of an exceeded limit, the units
+        /// should be contained in the key, not the value.  For example, rather than
+        /// {"instanceLimit": "100/request"}, should be returned as,
+        /// {"instanceLimitPerRequest": "100"}, if the client exceeds the number of
+        /// instances that can be created in a single (batch) request.
+        metadata: BTreeMap<String, String>,
+    },
+    /// Describes when the clients can retry a failed request. Clients could
+    /// ignore the recommendation here or retry when this information is missing
+    /// from error responses.
+    ///
+    /// It's always recommended that clients should use exponential backoff when
+    /// retrying.
+    ///
+    /// Clients should wait until `retry_delay` amount of time has passed since
+    /// receiving the error response before retrying.  If retrying requests also
+    /// fail, clients should use an exponential backoff scheme to gradually increase
+    /// the delay between retries based on `retry_delay`, until either a maximum
+    /// number of retries have been reached or a maximum retry delay cap has been
+    /// reached.
+    RetryInfo {
+        /// Clients should wait at least this long between retrying the same request.
+        retry_delay: Option<Duration>,
+    },
+    /// Describes how a quota check failed.
+    ///
+    /// For example if a daily limit was exceeded for the calling project,
+    /// a service could respond with a `QuotaFailure` detail containing the project
+    /// id and the description of the quota limit that was exceeded.  If the
+    /// calling project hasn't enabled the service in the developer console, then
+    /// a service could respond with the project id and set `service_disabled`
+    /// to true.
+    ///
+    /// Also see `RetryInfo` and `Help` types for other details about handling a
+    /// quota failure.
+    QuotaFailure {
+        /// Describes all quota violations.
+        violations: Vec<QuotaViolation>,
+    },
+    /// Describes what preconditions have failed.
+    ///
+    /// For example, if an RPC failed because it required the Terms of Service to be
+    /// acknowledged, it could list the terms of service violation in the
+    /// `PreconditionFailure` message.
+    PreconditionFailure {
+        /// Describes all precondition violations.
+        violations: Vec<PreconditionViolation>,
+    },
     /// Describes violations in a client request. This error type focuses on the
     /// syntactic aspects of the request.    
     BadRequest {
         /// Describes all violations in a client request.
         field_violations: Vec<FieldViolation>,
     },
+    /// Contains metadata about the request that clients can attach when filing a bug
+    /// or providing other forms of feedback.
+    RequestInfo {
+        /// An opaque string that should only be interpreted by the service generating
+        /// it. For example, it can be used to identify requests in the service's logs.
+        request_id: String,
+        /// Any data that was used to serve this request. For example, an encrypted
+        /// stack trace that can be sent back to the service provider for debugging.
+        serving_data: Option<String>,
+    },
+    /// Describes the resource that is being accessed.
+    ResourceInfo {
+        /// A name for the type of resource being accessed, e.g. "sql table",
+        /// "cloud storage bucket", "file", "Google calendar"; or the type URL
+        /// of the resource: e.g. "type.googleapis.com/google.pubsub.v1.Topic".
+        resource_type: String,
+        /// The name of the resource being accessed.  For example, a shared calendar
+        /// name: "example.com_4fghdhgsrgh@group.calendar.google.com", if the current
+        /// error is [`Code::PermissionDenied`].
+        resource_name: String,
+        /// The owner of the resource (optional).
+        /// For example, "user:<owner email>" or "project:<Google developer project
+        /// id>".
+        owner: Option<String>,
+        /// Describes what error is encountered when accessing this resource.
+        /// For example, updating a cloud project may require the `writer` permission
+        /// on the developer console project.
+        description: Option<String>,
+    },
+    /// Provides links to documentation or for performing an out of band action.
+    ///
+    /// For example, if a quota check failed with an error indicating the calling
+    /// project hasn't enabled the accessed service, this can contain a URL pointing
+    /// directly to the right place in the developer console to flip the bit.
+    Help {
+        /// URL(s) pointing to additional information on handling the current error.
+        links: Vec<Link>,
+    },
     /// Describes additional debugging info.
     DebugInfo {
         /// The stack trace entries indicating where the error occurred.
@@ -606,12 +743,58 @@ pub enum ErrorDetails {
 }
 
 impl ErrorDetails {
+    pub fn error_info<R: AsRef<str>, D: AsRef<str>>(reason: R, domain: D) -> Self {
+        ErrorDetails::ErrorInfo {
+            reason: reason.as_ref().to_owned(),
+            domain: domain.as_ref().to_owned(),
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    pub fn retry_info(retry_delay: Duration) -> Self {
+        ErrorDetails::RetryInfo {
+            retry_delay: Some(retry_delay),
+        }
+    }
+
+    pub fn quota_failure(violation: QuotaViolation) -> Self {
+        ErrorDetails::QuotaFailure {
+            violations: vec![violation],
+        }
+    }
+
+    pub fn precondition_failure(violation: PreconditionViolation) -> Self {
+        ErrorDetails::PreconditionFailure {
+            violations: vec![violation],
+        }
+    }
+
     pub fn bad_request(field_violation: FieldViolation) -> Self {
         ErrorDetails::BadRequest {
             field_violations: vec![field_violation],
         }
     }
 
+    pub fn request_info<R: AsRef<str>>(request_id: R) -> Self {
+        ErrorDetails::RequestInfo {
+            request_id: request_id.as_ref().to_owned(),
+            serving_data: None,
+        }
+    }
+
+    pub fn resource_info<T: AsRef<str>, N: AsRef<str>>(resource_type: T, resource_name: N) -> Self {
+        ErrorDetails::ResourceInfo {
+            resource_type: resource_type.as_ref().to_owned(),
+            resource_name: resource_name.as_ref().to_owned(),
+            owner: None,
+            description: None,
+        }
+    }
+
+    pub fn help(link: Link) -> Self {
+        ErrorDetails::Help { links: vec![link] }
+    }
+
     pub fn debug_info<D: AsRef<str>>(detail: D) -> Self {
         ErrorDetails::DebugInfo {
             stack_entries: None,
@@ -625,15 +808,192 @@ impl ErrorDetails {
             message: message.as_ref().to_owned(),
         }
     }
+
+    /// Returns the fully-qualified protobuf message name, e.g.,
+    /// `google.rpc.BadRequest`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ErrorDetails::ErrorInfo { .. } => "google.rpc.ErrorInfo",
+            ErrorDetails::RetryInfo { .. } => "google.rpc.RetryInfo",
+            ErrorDetails::QuotaFailure { .. } => "google.rpc.QuotaFailure",
+            ErrorDetails::PreconditionFailure { .. } => "google.rpc.PreconditionFailure",
+            ErrorDetails::BadRequest { .. } => "google.rpc.BadRequest",
+            ErrorDetails::RequestInfo { .. } => "google.rpc.RequestInfo",
+            ErrorDetails::ResourceInfo { .. } => "google.rpc.ResourceInfo",
+            ErrorDetails::Help { .. } => "google.rpc.Help",
+            ErrorDetails::DebugInfo { .. } => "google.rpc.DebugInfo",
+            ErrorDetails::LocalizedMessage { .. } => "google.rpc.LocalizedMessage",
+        }
+    }
+
+    /// Adds a `key` and `value` to the metadata of a `ErrorDetails::ErrorInfo`.
+    /// Other kinds of details are returned unchanged.
+    pub fn with_metadata<K: AsRef<str>, V: AsRef<str>>(mut self, key: K, value: V) -> Self {
+        if let ErrorDetails::ErrorInfo { metadata, .. } = &mut self {
+            metadata.insert(key.as_ref().to_owned(), value.as_ref().to_owned());
+        }
+        self
+    }
 }
 
-// TODO: Replace strum with a more detailed display implementation.
+/// Renders a readable summary of the details on a single line, e.g.,
+/// `bad request: name: Too long, age: Must be positive`.
+///
+/// The alternate form, `{:#}`, renders each item of the details on its own
+/// indented line.
 impl fmt::Display for ErrorDetails {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.into())
+        let mut items: Vec<String> = Vec::new();
+        let title = match self {
+            ErrorDetails::ErrorInfo {
+                reason,
+                domain,
+                metadata,
+            } => {
+                items.extend(metadata.iter().map(|(key, value)| format!("{key}={value}")));
+                format!("error info: {reason} in {domain}")
+            }
+            ErrorDetails::RetryInfo { retry_delay } => match retry_delay {
+                Some(retry_delay) => format!("retry info: retry after {retry_delay:?}"),
+                None => "retry info".to_owned(),
+            },
+            ErrorDetails::QuotaFailure { violations } => {
+                items.extend(violations.iter().map(|violation| {
+                    described(&violation.subject, violation.description.as_deref())
+                }));
+                "quota failure".to_owned()
+            }
+            ErrorDetails::PreconditionFailure { violations } => {
+                items.extend(violations.iter().map(|violation| {
+                    described(
+                        &format!("{} {}", violation.r#type, violation.subject),
+                        violation.description.as_deref(),
+                    )
+                }));
+                "precondition failure".to_owned()
+            }
+            ErrorDetails::BadRequest { field_violations } => {
+                items.extend(field_violations.iter().map(|violation| {
+                    described(
+                        &violation.field.to_string(),
+                        violation.description.as_deref(),
+                    )
+                }));
+                "bad request".to_owned()
+            }
+            ErrorDetails::RequestInfo {
+                request_id,
+                serving_data,
+            } => {
+                items.extend(
+                    serving_data
+                        .iter()
+                        .map(|data| format!("serving data: {data}")),
+                );
+                format!("request info: {request_id}")
+            }
+            ErrorDetails::ResourceInfo {
+                resource_type,
+                resource_name,
+                owner,
+                description,
+            } => {
+                items.extend(owner.iter().map(|owner| format!("owner: {owner}")));
+                items.extend(description.iter().cloned());
+                format!("resource info: {resource_type} {resource_name}")
+            }
+            ErrorDetails::Help { links } => {
+                items.extend(links.iter().map(|link| match &link.description {
+                    Some(description) => format!("{description}: {}", link.url),
+                    None => link.url.clone(),
+                }));
+                "help".to_owned()
+            }
+            ErrorDetails::DebugInfo {
+                stack_entries,
+                detail,
+            } => {
+                if f.alternate() {
+                    items.extend(
+                        stack_entries
+                            .iter()
+                            .flatten()
+                            .map(|entry| format!("at {entry}")),
+                    );
+                }
+                match detail {
+                    Some(detail) => format!("debug info: {detail}"),
+                    None => "debug info".to_owned(),
+                }
+            }
+            ErrorDetails::LocalizedMessage { locale, message } => {
+                format!("localized message: [{locale}] {message}")
+            }
+        };
+        f.write_str(&title)?;
+        if f.alternate() {
+            for item in items {
+                write!(f, "\n  {item}")?;
+            }
+        } else if !items.is_empty() {
+            write!(f, ": {}", items.join(", "))?;
+        }
+        Ok(())
     }
 }
 
+fn described(subject: &str, description: Option<&str>) -> String {
+    match description {
+        Some(description) => format!("{subject}: {description}"),
+        None => subject.to_owned(),
+    }
+}
+
+/// A message type used to describe a single quota violation.  For example, a
+/// daily quota or a custom quota that was exceeded.
+#[derive(Clone, Debug)]
+pub struct QuotaViolation {
+    /// The subject on which the quota check failed.
+    /// For example, "clientip:<ip address of client>" or "project:<Google
+    /// developer project id>".
+    pub subject: String,
+    /// A description of how the quota check failed. Clients can use this
+    /// description to find more about the quota configuration in the service's
+    /// public documentation, or find the relevant quota limit to adjust through
+    /// developer console.
+    ///
+    /// For example: "Service disabled" or "Daily Limit for read operations
+    /// exceeded".
+    pub description: Option<String>,
+}
+
+/// A message type used to describe a single precondition failure.
+#[derive(Clone, Debug)]
+pub struct PreconditionViolation {
+    /// The type of PreconditionFailure. We recommend using a service-specific
+    /// enum type to define the supported precondition violation subjects. For
+    /// example, "TOS" for "Terms of Service violation".
+    pub r#type: String,
+    /// The subject, relative to the type, that failed.
+    /// For example, "google.com/cloud" relative to the "TOS" type would indicate
+    /// which terms of service is being referenced.
+    pub subject: String,
+    /// A description of how the precondition failed. Developers can use this
+    /// description to understand how to fix the failure.
+    ///
+    /// For example: "Terms of service not accepted".
+    pub description: Option<String>,
+}
+
+/// Describes a URL link.
+#[derive(Clone, Debug)]
+pub struct Link {
+    /// Describes what the link offers.
+    pub description: Option<String>,
+    /// The URL of the link.
+    pub url: String,
+}
+
 /// A message type used to describe a single bad request field.
 #[derive(Clone, Debug)]
 pub struct FieldViolation {
@@ -680,51 +1040,199 @@ pub struct FieldViolation {
     pub description: Option<String>,
 }
 
-#[derive(Clone, Debug)]
+/// A path to a field in a request, rendered in a JavaScript-like syntax, e.g.,
+/// `family.children[3].nicknames["joe"]`.
+///
+/// The rendered path can be parsed back with [`FromStr`], which restores an
+/// equal [`Field`]. To keep the round trip exact, a `\`, `.` or `[` in a
+/// property name and a `\` or `"` in a map key are escaped with a `\`.
+///
+/// A path that cannot be parsed, e.g., one of an upstream error, is kept as an
+/// [unparsed](Field::unparsed) field that renders exactly as it was given.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Field {
     path_reversed: Vec<Property>,
+    unparsed: bool,
 }
 
 impl Field {
     pub fn new(property: Property) -> Self {
         Field {
             path_reversed: vec![property],
+            unparsed: false,
+        }
+    }
+
+    /// Returns a field of the unparsed `path` as a single [`Property::Member`],
+    /// which is rendered unchanged rather than escaped.
+    pub fn unparsed<P: AsRef<str>>(path: P) -> Self {
+        Field {
+            path_reversed: vec![Property::Member {
+                name: path.as_ref().to_owned(),
+            }],
+            unparsed: true,
         }
     }
 
+    /// Whether the field is a path that could not be parsed, see
+    /// [`Field::unparsed`].
+    pub fn is_unparsed(&self) -> bool {
+        self.unparsed
+    }
+
+    /// Adds `context` as the parent of the path. The path of an unparsed field
+    /// becomes the name of a member of `context`.
     pub fn with_context(mut self, context: Property) -> Self {
         self.path_reversed.push(context);
+        self.unparsed = false;
         self
     }
+
+    /// Returns the properties of the path from the root to the leaf.
+    pub fn properties(&self) -> impl DoubleEndedIterator<Item = &Property> + ExactSizeIterator {
+        self.path_reversed.iter().rev()
+    }
+
+    /// Returns the outermost property of the path.
+    pub fn root(&self) -> &Property {
+        &self.path_reversed[self.path_reversed.len() - 1]
+    }
+
+    /// Returns the innermost property of the path, the one that
+    /// [`Field::new`] was created with.
+    pub fn leaf(&self) -> &Property {
+        &self.path_reversed[0]
+    }
+}
+
+impl From<Property> for Field {
+    fn from(value: Property) -> Self {
+        Field::new(value)
+    }
 }
 
 impl fmt::Display for Field {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for i in (0..self.path_reversed.len()).rev() {
-            write!(f, r#"{}"#, self.path_reversed.get(i).ok_or(fmt::Error)?,)?;
+        if self.unparsed {
+            return f.write_str(self.leaf().name());
+        }
+        for (i, property) in self.properties().enumerate() {
             if i > 0 {
-                write!(f, ".")?;
+                f.write_str(".")?;
             }
+            write!(f, "{property}")?;
         }
         Ok(())
     }
 }
 
-#[derive(Clone, Debug)]
+impl FromStr for Field {
+    type Err = Error;
+
+    /// Parses the path rendered by the [`fmt::Display`] implementation.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            |reason: &str| Error::invalid_argument(format!("Invalid field path, {reason}: {s}"));
+        let mut path = Vec::new();
+        let mut chars = s.chars().peekable();
+        loop {
+            let mut name = String::new();
+            while let Some(c) = chars.next_if(|c| *c != '.' && *c != '[') {
+                match c {
+                    '\\' => name.push(chars.next().ok_or_else(|| invalid("dangling escape"))?),
+                    c => name.push(c),
+                }
+            }
+            if name.is_empty() {
+                return Err(invalid("missing property name"));
+            }
+            let property = if chars.next_if_eq(&'[').is_none() {
+                Property::Member { name }
+            } else if chars.next_if_eq(&'"').is_some() {
+                let mut key = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            key.push(chars.next().ok_or_else(|| invalid("dangling escape"))?)
+                        }
+                        Some(c) => key.push(c),
+                        None => return Err(invalid("unterminated map key")),
+                    }
+                }
+                if chars.next() != Some(']') {
+                    return Err(invalid("expected ']' after map key"));
+                }
+                Property::MapMember { name, key }
+            } else {
+                let mut index = String::new();
+                while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                    index.push(c);
+                }
+                if chars.next() != Some(']') {
+                    return Err(invalid("expected ']' after array index"));
+                }
+                let index = index.parse().map_err(|_| invalid("invalid array index"))?;
+                Property::ArrayMember { name, index }
+            };
+            path.push(property);
+            match chars.next() {
+                None => break,
+                Some('.') => continue,
+                Some(_) => return Err(invalid("expected '.' between properties")),
+            }
+        }
+        path.reverse();
+        Ok(Field {
+            path_reversed: path,
+            unparsed: false,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Property {
     Member { name: String },
     MapMember { name: String, key: String },
     ArrayMember { name: String, index: usize },
 }
 
+impl Property {
+    /// Returns the name of the member the property refers to.
+    pub fn name(&self) -> &str {
+        match self {
+            Property::Member { name } => name,
+            Property::MapMember { name, .. } => name,
+            Property::ArrayMember { name, .. } => name,
+        }
+    }
+}
+
 impl fmt::Display for Property {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = escape(self.name(), &['\\', '.', '[']);
         match self {
-            Property::Member { name } => write!(f, r#"{}"#, name),
-            Property::MapMember { name, key } => write!(f, r#"{}["{}"]"#, name, key),
-            Property::ArrayMember { name, index } => write!(f, r#"{}[{}]"#, name, index),
+            Property::Member { .. } => write!(f, r#"{}"#, name),
+            Property::MapMember { key, .. } => {
+                write!(f, r#"{}["{}"]"#, name, escape(key, &['\\', '"']))
+            }
+            Property::ArrayMember { index, .. } => write!(f, r#"{}[{}]"#, name, index),
+        }
+    }
+}
+
+fn escape<'a>(value: &'a str, special: &[char]) -> std::borrow::Cow<'a, str> {
+    if !value.contains(special) {
+        return value.into();
+    }
+    let mut escaped = String::with_capacity(value.len() + 1);
+    for c in value.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
         }
+        escaped.push(c);
     }
+    escaped.into()
 }
 
 /// A request for inter-module communication.
@@ -751,15 +1259,339 @@ mod tests {
 
     #[test]
     fn error_display() {
-        let error = Error::internal("Something bad happened").with_error("Invalid operation");
-        assert_eq!(error.to_string(), "INTERNAL");
-        assert!(error
-            .inner()
-            .details
-            .as_ref()
-            .expect("some error details")
+        let error = Error::internal("Something bad happened")
+            .with_details(ErrorDetails::debug_info("Invalid operation"));
+        assert_eq!(
+            error.to_string(),
+            "INTERNAL: Something bad happened (debug info: Invalid operation)"
+        );
+        assert_eq!(
+            Error::internal("disk full").to_string(),
+            "INTERNAL: disk full"
+        );
+        assert_eq!(
+            Error::new(Code::Unknown, ErrorStatus::default()).to_string(),
+            "UNKNOWN"
+        );
+    }
+
+    #[test]
+    fn error_size() {
+        assert_eq!(
+            std::mem::size_of::<Error>(),
+            2 * std::mem::size_of::<usize>()
+        );
+        assert_eq!(
+            std::mem::size_of::<Result<()>>(),
+            std::mem::size_of::<Error>()
+        );
+        assert!(matches!(
+            Error::with_code(Code::NotFound, "No greeting")
+                .inner()
+                .message,
+            Some(Cow::Borrowed("No greeting"))
+        ));
+        assert!(matches!(
+            Error::with_code(Code::NotFound, format!("No greeting {}", 1))
+                .inner()
+                .message,
+            Some(Cow::Owned(_))
+        ));
+        let name = String::from("Kris");
+        assert_eq!(
+            Error::not_found(&name).to_string(),
+            Error::not_found(name.as_str()).to_string()
+        );
+    }
+
+    #[test]
+    fn error_source() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset by peer");
+        let error = Error::unavailable("Greeter unavailable")
+            .with_error(Error::internal("Connection lost").with_error(io_error));
+        assert_eq!(error.to_string(), "UNAVAILABLE: Greeter unavailable");
+        assert_eq!(
+            error::Error::source(&error).map(ToString::to_string),
+            Some("INTERNAL: Connection lost".to_string())
+        );
+        assert_eq!(
+            error
+                .find_source::<std::io::Error>()
+                .map(std::io::Error::kind),
+            Some(std::io::ErrorKind::ConnectionReset)
+        );
+        assert!(error.find_source::<std::fmt::Error>().is_none());
+        assert!(error.inner().details.is_none());
+        assert!(matches!(
+            error.inner().encoded_details().as_slice(),
+            [ErrorDetails::DebugInfo { detail: Some(detail), .. }]
+                if detail == "INTERNAL: Connection lost: reset by peer"
+        ));
+    }
+
+    #[test]
+    fn error_source_chained() {
+        let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "no such file");
+        let error = Error::internal("Greetings unavailable")
+            .with_error(io_error)
+            .with_error("Reading greetings");
+        let mut causes = Vec::new();
+        let mut cause = error::Error::source(&error);
+        while let Some(error) = cause {
+            causes.push(error.to_string());
+            cause = error.source();
+        }
+        assert_eq!(causes, ["Reading greetings", "no such file"]);
+        assert_eq!(
+            error
+                .find_source::<std::io::Error>()
+                .map(std::io::Error::kind),
+            Some(std::io::ErrorKind::NotFound)
+        );
+    }
+
+    #[test]
+    fn error_backtrace() {
+        let error = Error::new(
+            Code::Internal,
+            ErrorStatus::default()
+                .with_message("Greeting lost")
+                .with_backtrace(Backtrace::force_capture()),
+        );
+        assert!(error.inner().encoded_details().is_empty());
+        let stack_entries = error.inner().stack_entries().expect("stack entries");
+        assert!(stack_entries
             .iter()
-            .any(|d| &d.to_string() == "DEBUG_INFO"));
+            .any(|entry| entry.contains("tests::error_backtrace")));
+        assert_eq!(error.to_string(), "INTERNAL: Greeting lost");
+        let rendered = format!("{error:#}");
+        assert!(rendered.starts_with("INTERNAL: Greeting lost\n  debug info: backtrace\n    at "));
+        assert_eq!(rendered.lines().count(), stack_entries.len() + 2);
+
+        let error = error.with_stack_entries();
+        assert!(error.inner().backtrace.is_none());
+        assert!(matches!(
+            error.inner().encoded_details().as_slice(),
+            [ErrorDetails::DebugInfo {
+                stack_entries: Some(encoded),
+                detail: Some(detail),
+            }] if *encoded == stack_entries && detail == "backtrace"
+        ));
+        assert_eq!(format!("{error:#}"), rendered);
+    }
+
+    #[test]
+    fn error_display_alternate() {
+        let error = Error::new(
+            Code::InvalidArgument,
+            ErrorStatus::default().with_message("Invalid greeting"),
+        )
+        .with_details(ErrorDetails::BadRequest {
+            field_violations: vec![
+                FieldViolation {
+                    field: Field::new(Property::Member {
+                        name: "name".to_string(),
+                    })
+                    .with_context(Property::ArrayMember {
+                        name: "recipients".to_string(),
+                        index: 1,
+                    }),
+                    description: Some("Too long".to_string()),
+                },
+                FieldViolation {
+                    field: Field::new(Property::Member {
+                        name: "age".to_string(),
+                    }),
+                    description: None,
+                },
+            ],
+        })
+        .with_details(ErrorDetails::localized_message("fr-CA", "Nom trop long"))
+        .with_details(ErrorDetails::DebugInfo {
+            stack_entries: Some(vec!["greeter::greet".to_string()]),
+            detail: Some("Name has 300 characters".to_string()),
+        });
+
+        assert_eq!(
+            error.to_string(),
+            "INVALID_ARGUMENT: Invalid greeting (\
+             bad request: recipients[1].name: Too long, age; \
+             localized message: [fr-CA] Nom trop long; \
+             debug info: Name has 300 characters)"
+        );
+        assert_eq!(
+            format!("{error:#}"),
+            "INVALID_ARGUMENT: Invalid greeting
+  bad request
+    recipients[1].name: Too long
+    age
+  localized message: [fr-CA] Nom trop long
+  debug info: Name has 300 characters
+    at greeter::greet"
+        );
+    }
+
+    #[test]
+    fn error_status_with_details() {
+        let error = Error::resource_exhausted("Too many greetings")
+            .with_details(
+                ErrorDetails::error_info("RATE_LIMIT_EXCEEDED", "greeter.appbiotic.com")
+                    .with_metadata("service", "greeter"),
+            )
+            .with_details(ErrorDetails::retry_info(Duration::from_secs(30)))
+            .with_details(ErrorDetails::help(Link {
+                description: Some("Quota documentation".to_string()),
+                url: "https://appbiotic.com/docs/quota".to_string(),
+            }));
+
+        let details = error.inner().details.as_ref().expect("some error details");
+        assert_eq!(details.len(), 3);
+        match &details[0] {
+            ErrorDetails::ErrorInfo {
+                reason,
+                domain,
+                metadata,
+            } => {
+                assert_eq!(reason, "RATE_LIMIT_EXCEEDED");
+                assert_eq!(domain, "greeter.appbiotic.com");
+                assert_eq!(metadata.get("service").map(String::as_str), Some("greeter"));
+            }
+            other => panic!("unexpected details: {other:?}"),
+        }
+        assert!(matches!(
+            details[1],
+            ErrorDetails::RetryInfo {
+                retry_delay: Some(delay)
+            } if delay == Duration::from_secs(30)
+        ));
+        assert_eq!(
+            details[2].to_string(),
+            "help: Quota documentation: https://appbiotic.com/docs/quota"
+        );
+    }
+
+    #[cfg(feature = "with-tonic")]
+    #[test]
+    fn tonic_status_round_trip() {
+        let error = Error::invalid_argument("Invalid greeting")
+            .with_details(ErrorDetails::bad_request(FieldViolation {
+                field: Field::new(Property::Member {
+                    name: "name".to_string(),
+                }),
+                description: Some("Too long".to_string()),
+            }))
+            .with_details(ErrorDetails::localized_message("fr-CA", "Nom trop long"))
+            .with_error("Name has 300 characters");
+
+        let redacted = Error::try_from(error.clone().into_tonic_status()).expect("converted");
+        assert_eq!(redacted.inner().details.as_ref().map(Vec::len), Some(2));
+
+        let status = error.into_tonic_status_with(&redact::Redactor::trusted());
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert_eq!(status.message(), "Invalid greeting");
+        let mut headers = tonic::metadata::MetadataMap::new().into_headers();
+        status.add_header(&mut headers).expect("status headers");
+        assert!(headers.contains_key("grpc-status-details-bin"));
+        let status = tonic::Status::from_header_map(&headers).expect("status from headers");
+
+        let error = Error::try_from(status).expect("converted status");
+        assert_eq!(error.code(), Code::InvalidArgument);
+        assert_eq!(error.inner().message.as_deref(), Some("Invalid greeting"));
+        let details = error.inner().details.as_ref().expect("some error details");
+        assert!(matches!(
+            &details[0],
+            ErrorDetails::BadRequest { field_violations }
+                if field_violations[0].field.to_string() == "name"
+        ));
+        assert!(matches!(
+            &details[1],
+            ErrorDetails::LocalizedMessage { locale, message }
+                if locale == "fr-CA" && message == "Nom trop long"
+        ));
+        assert!(matches!(
+            &details[2],
+            ErrorDetails::DebugInfo { detail: Some(detail), .. }
+                if detail == "Name has 300 characters"
+        ));
+    }
+
+    #[cfg(feature = "with-tonic")]
+    #[test]
+    fn tonic_status_without_details() {
+        let status = Error::not_found("No greeting").into_tonic_status();
+        assert!(status.details().is_empty());
+        let error = Error::try_from(status).expect("converted status");
+        assert_eq!(error.code(), Code::NotFound);
+        assert!(error.inner().details.is_none());
+        assert!(Error::try_from(tonic::Status::new(tonic::Code::Ok, "")).is_err());
+    }
+
+    #[cfg(feature = "with-tonic")]
+    #[test]
+    fn tonic_status_with_malformed_details() {
+        let status = tonic::Status::with_details(
+            tonic::Code::Unavailable,
+            "Greeter is down",
+            bytes::Bytes::from_static(b"\xff\xff\xff garbage"),
+        );
+        let error = Error::try_from(status).expect("converted status");
+        assert_eq!(error.code(), Code::Unavailable);
+        assert_eq!(error.inner().message.as_deref(), Some("Greeter is down"));
+        assert!(error.inner().details.is_none());
+    }
+
+    #[cfg(feature = "with-http")]
+    #[test]
+    fn error_from_http_status() {
+        let cases = [
+            (400, Code::InvalidArgument),
+            (404, Code::NotFound),
+            (409, Code::Aborted),
+            (418, Code::FailedPrecondition),
+            (429, Code::ResourceExhausted),
+            (502, Code::Internal),
+            (503, Code::Unavailable),
+            (200, Code::Unknown),
+        ];
+        for (status, code) in cases {
+            let status = http::StatusCode::from_u16(status).expect("status code");
+            assert_eq!(Error::from_http_status(status).code(), code, "{status}");
+        }
+        assert_eq!(
+            Error::from_http_status(http::StatusCode::NOT_FOUND)
+                .inner()
+                .message
+                .as_deref(),
+            Some("HTTP 404 Not Found")
+        );
+    }
+
+    #[cfg(feature = "with-http")]
+    #[test]
+    fn error_from_http_response() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            http::header::RETRY_AFTER,
+            http::HeaderValue::from_static("30"),
+        );
+        let error = Error::from_http_response(
+            http::StatusCode::SERVICE_UNAVAILABLE,
+            &headers,
+            b"upstream is restarting",
+        );
+        assert_eq!(error.code(), Code::Unavailable);
+        let details = error.inner().details.as_ref().expect("some error details");
+        assert!(matches!(
+            details[0],
+            ErrorDetails::RetryInfo {
+                retry_delay: Some(delay)
+            } if delay == Duration::from_secs(30)
+        ));
+        assert!(matches!(
+            &details[1],
+            ErrorDetails::DebugInfo { detail: Some(detail), .. }
+                if detail == "upstream is restarting"
+        ));
     }
 
     #[test]
@@ -807,4 +1639,66 @@ mod tests {
             r#"family.children[3].nicknames["joe"]"#
         );
     }
+
+    #[test]
+    fn field_from_str() {
+        let field: Field = r#"family.children[3].nicknames["joe"]"#
+            .parse()
+            .expect("parsed field");
+        let properties: Vec<&Property> = field.properties().collect();
+        assert_eq!(
+            properties,
+            [
+                &Property::Member {
+                    name: "family".to_string()
+                },
+                &Property::ArrayMember {
+                    name: "children".to_string(),
+                    index: 3
+                },
+                &Property::MapMember {
+                    name: "nicknames".to_string(),
+                    key: "joe".to_string()
+                },
+            ]
+        );
+        assert_eq!(field.root().name(), "family");
+        assert_eq!(field.leaf().name(), "nicknames");
+    }
+
+    #[test]
+    fn field_round_trip_with_escapes() {
+        let field = Field::new(Property::MapMember {
+            name: "labels".to_string(),
+            key: r#"app.kubernetes.io/"name"\x"#.to_string(),
+        })
+        .with_context(Property::Member {
+            name: "odd.name[0]".to_string(),
+        });
+        let rendered = field.to_string();
+        assert_eq!(
+            rendered,
+            r#"odd\.name\[0].labels["app.kubernetes.io/\"name\"\\x"]"#
+        );
+        assert_eq!(rendered.parse::<Field>().expect("parsed field"), field);
+    }
+
+    #[test]
+    fn field_from_str_rejects_invalid_paths() {
+        for path in [
+            "",
+            "family.",
+            ".family",
+            "children[",
+            "children[x]",
+            "children[3",
+            r#"nicknames["joe"#,
+            r#"nicknames["joe"x"#,
+            "children[3][4]",
+            "children[3]x",
+            "family\\",
+        ] {
+            assert!(path.parse::<Field>().is_err(), "{path}");
+        }
+    }
 }
@@ -0,0 +1,327 @@
+//! Renderings of a [`Field`] path for the different kinds of clients.
+//!
+//! | Syntax          | Example                               | Used by                          |
+//! | :-------------- | :------------------------------------ | :------------------------------- |
+//! | [`Dotted`]      | `family.children[3].nicknames["joe"]` | `Display`, `google.rpc` statuses |
+//! | [`JsonPointer`] | `/family/children/3/nicknames/joe`    | `invalid-params` of problem+json |
+//!
+//! The [`Dotted`] syntax is also the protobuf field path syntax of a
+//! `google.rpc.BadRequest`. Names are rendered as they are. Any syntax can be
+//! combined with a [`Case`] transform using [`WithCase`], e.g., to render the
+//! snake_case names of protobuf fields.
+//!
+//! ```
+//! use appbiotic_code_error::{
+//!     path::{Case, Dotted, FieldSyntax, JsonPointer, WithCase},
+//!     Field, Property,
+//! };
+//!
+//! let field = Field::new(Property::Member {
+//!     name: "nickName".to_string(),
+//! })
+//! .with_context(Property::ArrayMember {
+//!     name: "children".to_string(),
+//!     index: 3,
+//! });
+//! assert_eq!(JsonPointer.render(&field), "/children/3/nickName");
+//! assert_eq!(
+//!     WithCase::new(Dotted, Case::Snake).render(&field),
+//!     "children[3].nick_name"
+//! );
+//! assert_eq!(
+//!     WithCase::new(JsonPointer, Case::Snake).render(&field),
+//!     "/children/3/nick_name"
+//! );
+//! assert_eq!(JsonPointer.parse("/children/3/nickName").unwrap(), field);
+//! ```
+
+use std::str::FromStr;
+
+use crate::{Error, Field, Property, Result};
+
+/// A syntax to render a [`Field`] in and parse it from.
+pub trait FieldSyntax {
+    fn render(&self, field: &Field) -> String;
+
+    fn parse(&self, s: &str) -> Result<Field>;
+}
+
+/// The JavaScript-like syntax of the [`Field`] `Display` and `FromStr`
+/// implementations.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Dotted;
+
+impl FieldSyntax for Dotted {
+    fn render(&self, field: &Field) -> String {
+        field.to_string()
+    }
+
+    fn parse(&self, s: &str) -> Result<Field> {
+        Field::from_str(s)
+    }
+}
+
+/// A JSON Pointer as defined by
+/// [RFC 6901](https://www.rfc-editor.org/rfc/rfc6901).
+///
+/// A pointer does not tell map keys from members, so parsing is lossy: a
+/// number following a member is parsed as an array index and any other
+/// reference token as a member.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonPointer;
+
+impl FieldSyntax for JsonPointer {
+    fn render(&self, field: &Field) -> String {
+        if field.is_unparsed() {
+            return field.to_string();
+        }
+        let mut pointer = String::new();
+        for property in field.properties() {
+            pointer.push('/');
+            pointer.push_str(&escape_token(property.name()));
+            match property {
+                Property::Member { .. } => {}
+                Property::MapMember { key, .. } => {
+                    pointer.push('/');
+                    pointer.push_str(&escape_token(key));
+                }
+                Property::ArrayMember { index, .. } => {
+                    pointer.push('/');
+                    pointer.push_str(&index.to_string());
+                }
+            }
+        }
+        pointer
+    }
+
+    fn parse(&self, s: &str) -> Result<Field> {
+        let invalid =
+            |reason: &str| Error::invalid_argument(format!("Invalid JSON pointer, {reason}: {s}"));
+        let tokens = s
+            .strip_prefix('/')
+            .ok_or_else(|| invalid("expected '/' at the start"))?;
+        let mut path: Vec<Property> = Vec::new();
+        for token in tokens.split('/') {
+            let token = unescape_token(token).ok_or_else(|| invalid("invalid escape"))?;
+            if token.is_empty() {
+                return Err(invalid("missing reference token"));
+            }
+            let index = is_array_index(&token)
+                .then(|| token.parse::<usize>().ok())
+                .flatten();
+            let property = match (index, path.pop()) {
+                (Some(index), Some(Property::Member { name })) => {
+                    Property::ArrayMember { name, index }
+                }
+                (_, parent) => {
+                    path.extend(parent);
+                    Property::Member { name: token }
+                }
+            };
+            path.push(property);
+        }
+        Ok(field_from_path(path))
+    }
+}
+
+/// A naming convention of field names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    /// `lowerCamelCase`, as used by the JSON mapping of protobuf.
+    Camel,
+    /// `snake_case`, as used by protobuf field names.
+    Snake,
+}
+
+impl Case {
+    /// Converts `name` to the case.
+    pub fn convert(self, name: &str) -> String {
+        match self {
+            Case::Camel => {
+                let mut converted = String::with_capacity(name.len());
+                let mut upper = false;
+                for c in name.chars() {
+                    if c == '_' {
+                        upper = !converted.is_empty();
+                    } else if upper {
+                        converted.extend(c.to_uppercase());
+                        upper = false;
+                    } else {
+                        converted.push(c);
+                    }
+                }
+                converted
+            }
+            Case::Snake => {
+                let chars: Vec<char> = name.chars().collect();
+                let mut converted = String::with_capacity(name.len() + 4);
+                for (i, c) in chars.iter().enumerate() {
+                    if c.is_uppercase() && i > 0 {
+                        let previous = chars[i - 1];
+                        let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+                        if previous.is_lowercase()
+                            || previous.is_ascii_digit()
+                            || (previous.is_uppercase() && next_is_lower)
+                        {
+                            converted.push('_');
+                        }
+                    }
+                    converted.extend(c.to_lowercase());
+                }
+                converted
+            }
+        }
+    }
+}
+
+/// A syntax rendering names in a [`Case`].
+///
+/// Parsed names are converted to the case too, so that they can be compared
+/// with rendered ones.
+#[derive(Clone, Copy, Debug)]
+pub struct WithCase<S> {
+    syntax: S,
+    case: Case,
+}
+
+impl<S: FieldSyntax> WithCase<S> {
+    pub fn new(syntax: S, case: Case) -> Self {
+        WithCase { syntax, case }
+    }
+}
+
+impl<S: FieldSyntax> FieldSyntax for WithCase<S> {
+    fn render(&self, field: &Field) -> String {
+        self.syntax.render(&field.to_case(self.case))
+    }
+
+    fn parse(&self, s: &str) -> Result<Field> {
+        Ok(self.syntax.parse(s)?.to_case(self.case))
+    }
+}
+
+impl Field {
+    /// Returns the field with every property name converted to `case`. Map
+    /// keys are kept as they are.
+    pub fn to_case(&self, case: Case) -> Field {
+        if self.is_unparsed() {
+            return self.clone();
+        }
+        let path = self
+            .properties()
+            .map(|property| match property {
+                Property::Member { name } => Property::Member {
+                    name: case.convert(name),
+                },
+                Property::MapMember { name, key } => Property::MapMember {
+                    name: case.convert(name),
+                    key: key.clone(),
+                },
+                Property::ArrayMember { name, index } => Property::ArrayMember {
+                    name: case.convert(name),
+                    index: *index,
+                },
+            })
+            .collect();
+        field_from_path(path)
+    }
+}
+
+/// Builds a field from its properties from the root to the leaf.
+fn field_from_path(mut path: Vec<Property>) -> Field {
+    path.reverse();
+    Field {
+        path_reversed: path,
+        unparsed: false,
+    }
+}
+
+fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape_token(token: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next()? {
+                '0' => unescaped.push('~'),
+                '1' => unescaped.push('/'),
+                _ => return None,
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Some(unescaped)
+}
+
+/// Whether `token` is an array index as defined by RFC 6901: `0` or digits
+/// without a leading zero.
+fn is_array_index(token: &str) -> bool {
+    token == "0"
+        || (!token.starts_with('0')
+            && !token.is_empty()
+            && token.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn family_field() -> Field {
+        Field::new(Property::MapMember {
+            name: "nick_names".to_string(),
+            key: "jo/e~".to_string(),
+        })
+        .with_context(Property::ArrayMember {
+            name: "children".to_string(),
+            index: 3,
+        })
+        .with_context(Property::Member {
+            name: "family".to_string(),
+        })
+    }
+
+    #[test]
+    fn json_pointer() {
+        let field = family_field();
+        let pointer = JsonPointer.render(&field);
+        assert_eq!(pointer, "/family/children/3/nick_names/jo~1e~0");
+
+        let parsed = JsonPointer.parse(&pointer).expect("valid pointer");
+        assert_eq!(parsed.to_string(), "family.children[3].nick_names.jo/e~");
+        assert_eq!(
+            JsonPointer
+                .parse("/matrix/0/01")
+                .expect("valid pointer")
+                .to_string(),
+            "matrix[0].01"
+        );
+        for invalid in ["family", "/family//name", "/family/~2"] {
+            assert!(JsonPointer.parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn case_conversions() {
+        let cases = [
+            ("first_name", "firstName", "first_name"),
+            ("firstName", "firstName", "first_name"),
+            ("HTTPServer", "HTTPServer", "http_server"),
+            ("address2_line", "address2Line", "address2_line"),
+            ("_private", "private", "_private"),
+        ];
+        for (name, camel, snake) in cases {
+            assert_eq!(Case::Camel.convert(name), camel, "{name}");
+            assert_eq!(Case::Snake.convert(name), snake, "{name}");
+        }
+        assert_eq!(
+            WithCase::new(Dotted, Case::Camel)
+                .parse("family.nick_names")
+                .expect("valid path")
+                .to_string(),
+            "family.nickNames"
+        );
+    }
+}
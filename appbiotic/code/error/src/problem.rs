@@ -0,0 +1,373 @@
+//! Problem details for HTTP APIs as defined by
+//! [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457).
+//!
+//! An [`Error`] is rendered as an `application/problem+json` document with the
+//! following extension members:
+//!
+//! | Member           | Description                                            |
+//! | :--------------- | :----------------------------------------------------- |
+//! | `code`           | The gRPC code name, e.g., `INVALID_ARGUMENT`           |
+//! | `invalid-params` | The field violations of any `ErrorDetails::BadRequest` |
+//! | `details`        | All error details in the `google.rpc.Status` JSON form |
+//!
+//! The `name` of each of the `invalid-params` is the field rendered as a
+//! [`JsonPointer`].
+
+use std::{borrow::Cow, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    path::{FieldSyntax, JsonPointer},
+    redact::Redactor,
+    Code, Error, ErrorDetails, ErrorStatus, Field, FieldViolation, Result,
+};
+
+/// The media type of a problem details JSON document.
+pub const CONTENT_TYPE: &str = "application/problem+json";
+
+/// The problem type used when no further semantics than the HTTP status code
+/// are defined.
+pub const ABOUT_BLANK: &str = "about:blank";
+
+/// A problem details JSON object.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProblemDetails {
+    /// A URI reference that identifies the problem type.
+    #[serde(default = "about_blank")]
+    pub r#type: String,
+    /// A short, human-readable summary of the problem type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The HTTP status code generated by the origin server for this
+    /// occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// A human-readable explanation specific to this occurrence of the
+    /// problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// A URI reference that identifies the specific occurrence of the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Additional members of the problem details object.
+    #[serde(flatten)]
+    pub extensions: Map<String, Value>,
+}
+
+fn about_blank() -> String {
+    ABOUT_BLANK.to_owned()
+}
+
+impl ProblemDetails {
+    pub fn with_instance<I: AsRef<str>>(self, instance: I) -> Self {
+        ProblemDetails {
+            instance: Some(instance.as_ref().to_owned()),
+            ..self
+        }
+    }
+
+    /// Builds a response with the problem details as an
+    /// `application/problem+json` body.
+    pub fn into_response(self) -> http::Response<Vec<u8>> {
+        let status = self
+            .status
+            .and_then(|status| http::StatusCode::from_u16(status).ok())
+            .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = http::Response::new(serde_json::to_vec(&self).unwrap_or_default());
+        *response.status_mut() = status;
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static(CONTENT_TYPE),
+        );
+        response
+    }
+
+    /// Parses the problem details from the body of `response`, defaulting the
+    /// `status` member to the response status.
+    pub fn from_response<B: AsRef<[u8]>>(response: &http::Response<B>) -> Result<Self> {
+        let mut problem: ProblemDetails = serde_json::from_slice(response.body().as_ref())
+            .map_err(|err| {
+                Error::invalid_argument("Invalid problem details response body").with_error(err)
+            })?;
+        problem.status.get_or_insert(response.status().as_u16());
+        Ok(problem)
+    }
+}
+
+impl From<&Error> for ProblemDetails {
+    fn from(value: &Error) -> Self {
+        let status = value.http_status();
+        let details = value.inner().encoded_details();
+        let mut extensions = Map::new();
+        extensions.insert("code".to_owned(), Value::from(value.code().as_str()));
+        let invalid_params: Vec<Value> = details
+            .iter()
+            .filter_map(|details| match details {
+                ErrorDetails::BadRequest { field_violations } => Some(field_violations),
+                _ => None,
+            })
+            .flatten()
+            .map(|violation| {
+                let mut param = Map::new();
+                param.insert(
+                    "name".to_owned(),
+                    Value::from(JsonPointer.render(&violation.field)),
+                );
+                if let Some(description) = &violation.description {
+                    param.insert("reason".to_owned(), Value::from(description.as_str()));
+                }
+                Value::Object(param)
+            })
+            .collect();
+        if !invalid_params.is_empty() {
+            extensions.insert("invalid-params".to_owned(), Value::Array(invalid_params));
+        }
+        if !details.is_empty() {
+            extensions.insert(
+                "details".to_owned(),
+                serde_json::to_value(details).unwrap_or_default(),
+            );
+        }
+        ProblemDetails {
+            r#type: about_blank(),
+            title: Some(
+                status
+                    .canonical_reason()
+                    .unwrap_or("Client Closed Request")
+                    .to_owned(),
+            ),
+            status: Some(status.as_u16()),
+            detail: value.inner().message.as_deref().map(str::to_owned),
+            instance: None,
+            extensions,
+        }
+    }
+}
+
+impl TryFrom<ProblemDetails> for Error {
+    type Error = Error;
+
+    /// Converts the problem details into an error, preferring the `code` and
+    /// `details` extension members and falling back to the HTTP status and the
+    /// `invalid-params` member written by other servers. Any of the `details`
+    /// that fails to decode is skipped.
+    fn try_from(mut value: ProblemDetails) -> std::result::Result<Self, Self::Error> {
+        let code = value
+            .extensions
+            .get("code")
+            .and_then(Value::as_str)
+            .and_then(|code| Code::from_str(code).ok())
+            .or_else(|| {
+                value
+                    .status
+                    .and_then(|status| http::StatusCode::from_u16(status).ok())
+                    .map(Code::from_http_status)
+            })
+            .unwrap_or(Code::Unknown);
+        let details = match value.extensions.remove("details") {
+            Some(Value::Array(details)) => crate::google_json::decode_details(details),
+            _ => invalid_params_details(value.extensions.get("invalid-params")),
+        };
+        let status = ErrorStatus {
+            message: value.detail.or(value.title).map(Cow::from),
+            details: (!details.is_empty()).then_some(details),
+            source: None,
+            backtrace: None,
+        };
+        Ok(Error::new(code, status))
+    }
+}
+
+fn invalid_params_details(invalid_params: Option<&Value>) -> Vec<ErrorDetails> {
+    let field_violations: Vec<FieldViolation> = invalid_params
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|param| {
+            let name = param.get("name").and_then(Value::as_str)?;
+            let field = if name.starts_with('/') {
+                JsonPointer.parse(name)
+            } else {
+                Field::from_str(name)
+            };
+            let field = field.unwrap_or_else(|_| Field::unparsed(name));
+            Some(FieldViolation {
+                field,
+                description: param
+                    .get("reason")
+                    .and_then(Value::as_str)
+                    .map(str::to_owned),
+            })
+        })
+        .collect();
+    if field_violations.is_empty() {
+        Vec::new()
+    } else {
+        vec![ErrorDetails::BadRequest { field_violations }]
+    }
+}
+
+impl Error {
+    /// Builds an `application/problem+json` response for the error, redacted
+    /// by the default [`Redactor`].
+    pub fn into_problem_response(self) -> http::Response<Vec<u8>> {
+        self.into_problem_response_with(&Redactor::default())
+    }
+
+    /// Builds an `application/problem+json` response for the error, redacted
+    /// by `redactor`.
+    pub fn into_problem_response_with(self, redactor: &Redactor) -> http::Response<Vec<u8>> {
+        ProblemDetails::from(&redactor.redact(self)).into_response()
+    }
+
+    /// Parses an `application/problem+json` response into an error.
+    pub fn from_problem_response<B: AsRef<[u8]>>(response: &http::Response<B>) -> Result<Error> {
+        Error::try_from(ProblemDetails::from_response(response)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Property;
+
+    fn invalid_greeting() -> Error {
+        Error::invalid_argument("Invalid greeting")
+            .with_details(ErrorDetails::bad_request(FieldViolation {
+                field: Field::new(Property::Member {
+                    name: "name".to_string(),
+                })
+                .with_context(Property::ArrayMember {
+                    name: "recipients".to_string(),
+                    index: 1,
+                }),
+                description: Some("Too long".to_string()),
+            }))
+            .with_details(ErrorDetails::error_info(
+                "NAME_TOO_LONG",
+                "greeter.appbiotic.com",
+            ))
+    }
+
+    #[test]
+    fn error_into_problem_response() {
+        let response = invalid_greeting().into_problem_response();
+        assert_eq!(response.status(), http::StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get(http::header::CONTENT_TYPE),
+            Some(&http::HeaderValue::from_static(CONTENT_TYPE))
+        );
+        let body: Value = serde_json::from_slice(response.body()).expect("JSON body");
+        assert_eq!(
+            body,
+            json!({
+                "type": "about:blank",
+                "title": "Bad Request",
+                "status": 400,
+                "detail": "Invalid greeting",
+                "code": "INVALID_ARGUMENT",
+                "invalid-params": [
+                    { "name": "/recipients/1/name", "reason": "Too long" },
+                ],
+                "details": [
+                    {
+                        "@type": "type.googleapis.com/google.rpc.BadRequest",
+                        "fieldViolations": [
+                            { "field": "recipients[1].name", "description": "Too long" },
+                        ],
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                        "reason": "NAME_TOO_LONG",
+                        "domain": "greeter.appbiotic.com",
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn error_from_problem_response_round_trip() {
+        let response = Error::failed_precondition("Greeter is closed")
+            .with_details(ErrorDetails::localized_message("fr-CA", "Fermé"))
+            .into_problem_response();
+        let error = Error::from_problem_response(&response).expect("parsed problem");
+        assert_eq!(error.code(), Code::FailedPrecondition);
+        assert_eq!(error.inner().message.as_deref(), Some("Greeter is closed"));
+        assert!(matches!(
+            error.inner().details.as_deref(),
+            Some([ErrorDetails::LocalizedMessage { locale, .. }]) if locale == "fr-CA"
+        ));
+    }
+
+    #[test]
+    fn error_from_problem_skips_invalid_details() {
+        let problem: ProblemDetails = serde_json::from_value(json!({
+            "status": 429,
+            "code": "RESOURCE_EXHAUSTED",
+            "details": [
+                { "@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "soon" },
+                { "@type": "type.googleapis.com/google.rpc.RequestInfo", "requestId": "r-1" },
+            ],
+        }))
+        .expect("problem details");
+        let error = Error::try_from(problem).expect("converted problem");
+        assert_eq!(error.code(), Code::ResourceExhausted);
+        assert!(matches!(
+            error.inner().details.as_deref(),
+            Some([ErrorDetails::RequestInfo { request_id, .. }]) if request_id == "r-1"
+        ));
+    }
+
+    #[test]
+    fn error_from_third_party_problem_response() {
+        let body = json!({
+            "type": "https://example.net/validation-error",
+            "title": "Your request is not valid.",
+            "invalid-params": [
+                { "name": "age", "reason": "must be a positive integer" },
+            ],
+        });
+        let response = http::Response::builder()
+            .status(http::StatusCode::BAD_REQUEST)
+            .header(http::header::CONTENT_TYPE, CONTENT_TYPE)
+            .body(serde_json::to_vec(&body).expect("JSON body"))
+            .expect("response");
+
+        let error = Error::from_problem_response(&response).expect("parsed problem");
+        assert_eq!(error.code(), Code::InvalidArgument);
+        assert_eq!(
+            error.inner().message.as_deref(),
+            Some("Your request is not valid.")
+        );
+        match error.inner().details.as_deref() {
+            Some([ErrorDetails::BadRequest { field_violations }]) => {
+                assert_eq!(field_violations[0].field.to_string(), "age");
+                assert_eq!(
+                    field_violations[0].description.as_deref(),
+                    Some("must be a positive integer")
+                );
+            }
+            other => panic!("unexpected details: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_keeps_unparsed_invalid_params() {
+        let problem: ProblemDetails = serde_json::from_value(json!({
+            "status": 400,
+            "invalid-params": [{ "name": "/recipients//name", "reason": "Too long" }],
+        }))
+        .expect("problem details");
+        let error = Error::try_from(problem).expect("converted problem");
+        let problem = ProblemDetails::from(&error);
+        assert_eq!(
+            problem.extensions.get("invalid-params"),
+            Some(&json!([{ "name": "/recipients//name", "reason": "Too long" }]))
+        );
+    }
+}
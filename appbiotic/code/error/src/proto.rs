@@ -0,0 +1,750 @@
+//! Protobuf binary encoding of [`Error`] as a `google.rpc.Status` message with
+//! its [`ErrorDetails`] packed in `google.protobuf.Any` messages.
+//!
+//! The encoding is written directly against the protobuf wire format, see
+//! https://protobuf.dev/programming-guides/encoding/, so that no generated code
+//! or `build.rs` is required. Message and field numbers follow
+//! https://github.com/googleapis/googleapis/blob/f36c65081b19e0758ef5696feca27c7dcee5475e/google/rpc/status.proto
+//! and
+//! https://github.com/googleapis/googleapis/blob/f36c65081b19e0758ef5696feca27c7dcee5475e/google/rpc/error_details.proto.
+//!
+//! As in proto3, empty strings and zero values are not written, so an empty
+//! optional string decodes as `None`. A field of a `FieldViolation` that cannot
+//! be parsed back, e.g., an empty one, is kept [unparsed](Field::unparsed) and
+//! encoded unchanged.
+
+use std::{borrow::Cow, collections::BTreeMap, str::FromStr, time::Duration};
+
+use crate::{
+    Error, ErrorDetails, ErrorStatus, Field, FieldViolation, Link, PreconditionViolation,
+    QuotaViolation, Result,
+};
+
+/// The prefix of the `google.protobuf.Any` type URL for error details.
+pub const TYPE_URL_PREFIX: &str = "type.googleapis.com/";
+
+/// Encodes `error` as a `google.rpc.Status` message.
+pub fn encode_status(error: &Error) -> Vec<u8> {
+    let status = error.inner();
+    let mut writer = Writer::default();
+    writer.int32(1, error.code().value());
+    writer.string(2, status.message.as_deref());
+    for details in &status.encoded_details() {
+        writer.message(3, &encode_any(details));
+    }
+    writer.into_bytes()
+}
+
+/// Decodes a `google.rpc.Status` message into an [`Error`].
+///
+/// Details with a type URL other than the known `google.rpc` error details
+/// are skipped, the same way unknown fields are, and so are details that fail
+/// to decode. Only a malformed `google.rpc.Status` message itself fails.
+pub fn decode_status(bytes: &[u8]) -> Result<Error> {
+    let mut code = 0;
+    let mut message = None;
+    let mut details = Vec::new();
+    let mut reader = Reader::new(bytes);
+    while let Some((field, wire_type)) = reader.next_field()? {
+        match (field, wire_type) {
+            (1, WireType::Varint) => code = reader.int32()?,
+            (2, WireType::LengthDelimited) => message = Some(reader.string()?),
+            (3, WireType::LengthDelimited) => {
+                if let Ok(Some(decoded)) = decode_any(reader.bytes()?) {
+                    details.push(decoded);
+                }
+            }
+            (_, wire_type) => reader.skip(wire_type)?,
+        }
+    }
+    Error::from_code(
+        code,
+        ErrorStatus {
+            message: message.map(Cow::from),
+            details: (!details.is_empty()).then_some(details),
+            source: None,
+            backtrace: None,
+        },
+    )
+}
+
+/// Encodes `details` as a `google.protobuf.Any` message.
+pub fn encode_any(details: &ErrorDetails) -> Vec<u8> {
+    let mut writer = Writer::default();
+    writer.string(
+        1,
+        Some(&format!("{TYPE_URL_PREFIX}{}", details.type_name())),
+    );
+    writer.bytes(2, &encode_details(details));
+    writer.into_bytes()
+}
+
+/// Decodes a `google.protobuf.Any` message into [`ErrorDetails`], returning
+/// `None` when the packed message is not a known error detail type.
+pub fn decode_any(bytes: &[u8]) -> Result<Option<ErrorDetails>> {
+    let mut type_url = String::new();
+    let mut value: &[u8] = &[];
+    let mut reader = Reader::new(bytes);
+    while let Some((field, wire_type)) = reader.next_field()? {
+        match (field, wire_type) {
+            (1, WireType::LengthDelimited) => type_url = reader.string()?,
+            (2, WireType::LengthDelimited) => value = reader.bytes()?,
+            (_, wire_type) => reader.skip(wire_type)?,
+        }
+    }
+    let type_name = type_url.rsplit('/').next().unwrap_or_default();
+    decode_details(type_name, value)
+}
+
+fn encode_details(details: &ErrorDetails) -> Vec<u8> {
+    let mut writer = Writer::default();
+    match details {
+        ErrorDetails::ErrorInfo {
+            reason,
+            domain,
+            metadata,
+        } => {
+            writer.string(1, Some(reason));
+            writer.string(2, Some(domain));
+            for (key, value) in metadata {
+                let mut entry = Writer::default();
+                entry.string(1, Some(key));
+                entry.string(2, Some(value));
+                writer.message(3, &entry.into_bytes());
+            }
+        }
+        ErrorDetails::RetryInfo { retry_delay } => {
+            if let Some(retry_delay) = retry_delay {
+                let mut duration = Writer::default();
+                duration.int64(1, retry_delay.as_secs() as i64);
+                duration.int32(2, retry_delay.subsec_nanos() as i32);
+                writer.message(1, &duration.into_bytes());
+            }
+        }
+        ErrorDetails::QuotaFailure { violations } => {
+            for violation in violations {
+                let mut message = Writer::default();
+                message.string(1, Some(&violation.subject));
+                message.string(2, violation.description.as_deref());
+                writer.message(1, &message.into_bytes());
+            }
+        }
+        ErrorDetails::PreconditionFailure { violations } => {
+            for violation in violations {
+                let mut message = Writer::default();
+                message.string(1, Some(&violation.r#type));
+                message.string(2, Some(&violation.subject));
+                message.string(3, violation.description.as_deref());
+                writer.message(1, &message.into_bytes());
+            }
+        }
+        ErrorDetails::BadRequest { field_violations } => {
+            for violation in field_violations {
+                let mut message = Writer::default();
+                message.string(1, Some(&violation.field.to_string()));
+                message.string(2, violation.description.as_deref());
+                writer.message(1, &message.into_bytes());
+            }
+        }
+        ErrorDetails::RequestInfo {
+            request_id,
+            serving_data,
+        } => {
+            writer.string(1, Some(request_id));
+            writer.string(2, serving_data.as_deref());
+        }
+        ErrorDetails::ResourceInfo {
+            resource_type,
+            resource_name,
+            owner,
+            description,
+        } => {
+            writer.string(1, Some(resource_type));
+            writer.string(2, Some(resource_name));
+            writer.string(3, owner.as_deref());
+            writer.string(4, description.as_deref());
+        }
+        ErrorDetails::Help { links } => {
+            for link in links {
+                let mut message = Writer::default();
+                message.string(1, link.description.as_deref());
+                message.string(2, Some(&link.url));
+                writer.message(1, &message.into_bytes());
+            }
+        }
+        ErrorDetails::DebugInfo {
+            stack_entries,
+            detail,
+        } => {
+            for stack_entry in stack_entries.iter().flatten() {
+                writer.repeated_string(1, stack_entry);
+            }
+            writer.string(2, detail.as_deref());
+        }
+        ErrorDetails::LocalizedMessage { locale, message } => {
+            writer.string(1, Some(locale));
+            writer.string(2, Some(message));
+        }
+    }
+    writer.into_bytes()
+}
+
+fn decode_details(type_name: &str, bytes: &[u8]) -> Result<Option<ErrorDetails>> {
+    let mut reader = Reader::new(bytes);
+    let details = match type_name {
+        "google.rpc.ErrorInfo" => {
+            let mut reason = String::new();
+            let mut domain = String::new();
+            let mut metadata = BTreeMap::new();
+            while let Some((field, wire_type)) = reader.next_field()? {
+                match (field, wire_type) {
+                    (1, WireType::LengthDelimited) => reason = reader.string()?,
+                    (2, WireType::LengthDelimited) => domain = reader.string()?,
+                    (3, WireType::LengthDelimited) => {
+                        let [key, value] = decode_strings(reader.bytes()?)?;
+                        metadata.insert(key.unwrap_or_default(), value.unwrap_or_default());
+                    }
+                    (_, wire_type) => reader.skip(wire_type)?,
+                }
+            }
+            ErrorDetails::ErrorInfo {
+                reason,
+                domain,
+                metadata,
+            }
+        }
+        "google.rpc.RetryInfo" => {
+            let mut retry_delay = None;
+            while let Some((field, wire_type)) = reader.next_field()? {
+                match (field, wire_type) {
+                    (1, WireType::LengthDelimited) => {
+                        retry_delay = Some(decode_duration(reader.bytes()?)?)
+                    }
+                    (_, wire_type) => reader.skip(wire_type)?,
+                }
+            }
+            ErrorDetails::RetryInfo { retry_delay }
+        }
+        "google.rpc.QuotaFailure" => {
+            let mut violations = Vec::new();
+            while let Some((field, wire_type)) = reader.next_field()? {
+                match (field, wire_type) {
+                    (1, WireType::LengthDelimited) => {
+                        let [subject, description] = decode_strings(reader.bytes()?)?;
+                        violations.push(QuotaViolation {
+                            subject: subject.unwrap_or_default(),
+                            description,
+                        });
+                    }
+                    (_, wire_type) => reader.skip(wire_type)?,
+                }
+            }
+            ErrorDetails::QuotaFailure { violations }
+        }
+        "google.rpc.PreconditionFailure" => {
+            let mut violations = Vec::new();
+            while let Some((field, wire_type)) = reader.next_field()? {
+                match (field, wire_type) {
+                    (1, WireType::LengthDelimited) => {
+                        let [r#type, subject, description] = decode_strings(reader.bytes()?)?;
+                        violations.push(PreconditionViolation {
+                            r#type: r#type.unwrap_or_default(),
+                            subject: subject.unwrap_or_default(),
+                            description,
+                        });
+                    }
+                    (_, wire_type) => reader.skip(wire_type)?,
+                }
+            }
+            ErrorDetails::PreconditionFailure { violations }
+        }
+        "google.rpc.BadRequest" => {
+            let mut field_violations = Vec::new();
+            while let Some((field, wire_type)) = reader.next_field()? {
+                match (field, wire_type) {
+                    (1, WireType::LengthDelimited) => {
+                        let [field, description] = decode_strings(reader.bytes()?)?;
+                        let field = field.unwrap_or_default();
+                        field_violations.push(FieldViolation {
+                            field: Field::from_str(&field)
+                                .unwrap_or_else(|_| Field::unparsed(field)),
+                            description,
+                        });
+                    }
+                    (_, wire_type) => reader.skip(wire_type)?,
+                }
+            }
+            ErrorDetails::BadRequest { field_violations }
+        }
+        "google.rpc.RequestInfo" => {
+            let [request_id, serving_data] = decode_strings(bytes)?;
+            ErrorDetails::RequestInfo {
+                request_id: request_id.unwrap_or_default(),
+                serving_data,
+            }
+        }
+        "google.rpc.ResourceInfo" => {
+            let [resource_type, resource_name, owner, description] = decode_strings(bytes)?;
+            ErrorDetails::ResourceInfo {
+                resource_type: resource_type.unwrap_or_default(),
+                resource_name: resource_name.unwrap_or_default(),
+                owner,
+                description,
+            }
+        }
+        "google.rpc.Help" => {
+            let mut links = Vec::new();
+            while let Some((field, wire_type)) = reader.next_field()? {
+                match (field, wire_type) {
+                    (1, WireType::LengthDelimited) => {
+                        let [description, url] = decode_strings(reader.bytes()?)?;
+                        links.push(Link {
+                            description,
+                            url: url.unwrap_or_default(),
+                        });
+                    }
+                    (_, wire_type) => reader.skip(wire_type)?,
+                }
+            }
+            ErrorDetails::Help { links }
+        }
+        "google.rpc.DebugInfo" => {
+            let mut stack_entries = Vec::new();
+            let mut detail = None;
+            while let Some((field, wire_type)) = reader.next_field()? {
+                match (field, wire_type) {
+                    (1, WireType::LengthDelimited) => stack_entries.push(reader.string()?),
+                    (2, WireType::LengthDelimited) => detail = Some(reader.string()?),
+                    (_, wire_type) => reader.skip(wire_type)?,
+                }
+            }
+            ErrorDetails::DebugInfo {
+                stack_entries: (!stack_entries.is_empty()).then_some(stack_entries),
+                detail,
+            }
+        }
+        "google.rpc.LocalizedMessage" => {
+            let [locale, message] = decode_strings(bytes)?;
+            ErrorDetails::LocalizedMessage {
+                locale: locale.unwrap_or_default(),
+                message: message.unwrap_or_default(),
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(details))
+}
+
+/// Decodes a message made only of singular string fields numbered from 1 to
+/// `N`.
+fn decode_strings<const N: usize>(bytes: &[u8]) -> Result<[Option<String>; N]> {
+    let mut values = std::array::from_fn(|_| None);
+    let mut reader = Reader::new(bytes);
+    while let Some((field, wire_type)) = reader.next_field()? {
+        let index = field as usize;
+        if wire_type == WireType::LengthDelimited && (1..=N).contains(&index) {
+            values[index - 1] = Some(reader.string()?);
+        } else {
+            reader.skip(wire_type)?;
+        }
+    }
+    Ok(values)
+}
+
+fn decode_duration(bytes: &[u8]) -> Result<Duration> {
+    let mut seconds = 0;
+    let mut nanos = 0;
+    let mut reader = Reader::new(bytes);
+    while let Some((field, wire_type)) = reader.next_field()? {
+        match (field, wire_type) {
+            (1, WireType::Varint) => seconds = reader.varint()? as i64,
+            (2, WireType::Varint) => nanos = reader.int32()?,
+            (_, wire_type) => reader.skip(wire_type)?,
+        }
+    }
+    match (u64::try_from(seconds), u32::try_from(nanos)) {
+        (Ok(seconds), Ok(nanos)) if nanos < 1_000_000_000 => Ok(Duration::new(seconds, nanos)),
+        _ => Err(Error::invalid_argument(format!(
+            "Unsupported protobuf duration: {seconds}s {nanos}ns"
+        ))),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WireType {
+    Varint,
+    Fixed64,
+    LengthDelimited,
+    Fixed32,
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: WireType) {
+        let wire_type = match wire_type {
+            WireType::Varint => 0,
+            WireType::Fixed64 => 1,
+            WireType::LengthDelimited => 2,
+            WireType::Fixed32 => 5,
+        };
+        self.varint(u64::from(field << 3 | wire_type));
+    }
+
+    fn int32(&mut self, field: u32, value: i32) {
+        self.int64(field, i64::from(value));
+    }
+
+    fn int64(&mut self, field: u32, value: i64) {
+        if value != 0 {
+            self.key(field, WireType::Varint);
+            self.varint(value as u64);
+        }
+    }
+
+    /// Writes a singular string, skipping absent and empty values.
+    fn string(&mut self, field: u32, value: Option<&str>) {
+        if let Some(value) = value.filter(|value| !value.is_empty()) {
+            self.repeated_string(field, value);
+        }
+    }
+
+    /// Writes an element of a repeated string, which is kept even when empty.
+    fn repeated_string(&mut self, field: u32, value: &str) {
+        self.message(field, value.as_bytes());
+    }
+
+    fn bytes(&mut self, field: u32, value: &[u8]) {
+        if !value.is_empty() {
+            self.message(field, value);
+        }
+    }
+
+    fn message(&mut self, field: u32, value: &[u8]) {
+        self.key(field, WireType::LengthDelimited);
+        self.varint(value.len() as u64);
+        self.bytes.extend_from_slice(value);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    fn next_field(&mut self) -> Result<Option<(u32, WireType)>> {
+        if self.bytes.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let wire_type = match key & 0x07 {
+            0 => WireType::Varint,
+            1 => WireType::Fixed64,
+            2 => WireType::LengthDelimited,
+            5 => WireType::Fixed32,
+            other => {
+                return Err(Error::invalid_argument(format!(
+                    "Unsupported protobuf wire type: {other}"
+                )))
+            }
+        };
+        let field = u32::try_from(key >> 3)
+            .ok()
+            .filter(|field| *field > 0)
+            .ok_or_else(|| Error::invalid_argument("Invalid protobuf field number"))?;
+        Ok(Some((field, wire_type)))
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0;
+        for (i, byte) in self.bytes.iter().take(10).enumerate() {
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                self.bytes = &self.bytes[i + 1..];
+                return Ok(value);
+            }
+        }
+        Err(Error::invalid_argument("Invalid protobuf varint"))
+    }
+
+    fn int32(&mut self) -> Result<i32> {
+        // Negative values are sign-extended to 64 bits, so truncation restores
+        // the original value.
+        Ok(self.varint()? as i32)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = usize::try_from(self.varint()?)
+            .ok()
+            .filter(|len| *len <= self.bytes.len())
+            .ok_or_else(|| Error::invalid_argument("Truncated protobuf message"))?;
+        let (value, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| Error::invalid_argument("Invalid UTF-8 in protobuf string"))
+    }
+
+    fn skip(&mut self, wire_type: WireType) -> Result<()> {
+        let len = match wire_type {
+            WireType::Varint => return self.varint().map(|_| ()),
+            WireType::LengthDelimited => return self.bytes().map(|_| ()),
+            WireType::Fixed64 => 8,
+            WireType::Fixed32 => 4,
+        };
+        if self.bytes.len() < len {
+            return Err(Error::invalid_argument("Truncated protobuf message"));
+        }
+        self.bytes = &self.bytes[len..];
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{Code, Property};
+
+    /// Encoded with the reference protobuf implementation.
+    const BAD_REQUEST_STATUS: &str = concat!(
+        "08031210496e76616c6964206772656574696e671a3f0a29747970652e676f6f676c65617069732e636f",
+        "6d2f676f6f676c652e7270632e4261645265717565737412120a100a046e616d651208546f6f206c6f6e",
+        "671a360a28747970652e676f6f676c65617069732e636f6d2f676f6f676c652e7270632e526574727949",
+        "6e666f120a0a0808011080cab5ee01",
+    );
+
+    /// Encoded with the reference protobuf implementation.
+    const ERROR_INFO_STATUS: &str = concat!(
+        "08051a630a28747970652e676f6f676c65617069732e636f6d2f676f6f676c652e7270632e4572726f72",
+        "496e666f12370a0d4e414d455f544f4f5f4c4f4e471215677265657465722e61707062696f7469632e63",
+        "6f6d1a0f0a096d61784c656e677468120232301a490a2f747970652e676f6f676c65617069732e636f6d",
+        "2f676f6f676c652e7270632e4c6f63616c697a65644d65737361676512160a0566722d4341120d4e6f6d",
+        "2074726f70206c6f6e671a3a0a28747970652e676f6f676c65617069732e636f6d2f676f6f676c652e72",
+        "70632e4465627567496e666f120e0a046d61696e0a001204626f6f6d",
+    );
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("hex byte"))
+            .collect()
+    }
+
+    fn bad_request_error() -> Error {
+        Error::invalid_argument("Invalid greeting")
+            .with_details(ErrorDetails::bad_request(FieldViolation {
+                field: Field::new(Property::Member {
+                    name: "name".to_string(),
+                }),
+                description: Some("Too long".to_string()),
+            }))
+            .with_details(ErrorDetails::retry_info(Duration::from_millis(1500)))
+    }
+
+    fn error_info_error() -> Error {
+        Error::new(
+            Code::NotFound,
+            ErrorStatus::default()
+                .with_details(
+                    ErrorDetails::error_info("NAME_TOO_LONG", "greeter.appbiotic.com")
+                        .with_metadata("maxLength", "20"),
+                )
+                .with_localized_message("fr-CA", "Nom trop long")
+                .with_details(ErrorDetails::DebugInfo {
+                    stack_entries: Some(vec!["main".to_string(), String::new()]),
+                    detail: Some("boom".to_string()),
+                }),
+        )
+    }
+
+    #[test]
+    fn encode_status_matches_reference() {
+        assert_eq!(
+            encode_status(&bad_request_error()),
+            from_hex(BAD_REQUEST_STATUS)
+        );
+        assert_eq!(
+            encode_status(&error_info_error()),
+            from_hex(ERROR_INFO_STATUS)
+        );
+    }
+
+    #[test]
+    fn decode_status_round_trips_reference() {
+        for hex in [BAD_REQUEST_STATUS, ERROR_INFO_STATUS] {
+            let bytes = from_hex(hex);
+            let error = decode_status(&bytes).expect("decoded status");
+            assert_eq!(encode_status(&error), bytes);
+        }
+
+        let error = decode_status(&from_hex(BAD_REQUEST_STATUS)).expect("decoded status");
+        assert_eq!(error.code(), Code::InvalidArgument);
+        let details = error.inner().details.as_ref().expect("some error details");
+        match &details[0] {
+            ErrorDetails::BadRequest { field_violations } => {
+                assert_eq!(field_violations[0].field.to_string(), "name");
+                assert_eq!(field_violations[0].description.as_deref(), Some("Too long"));
+            }
+            other => panic!("unexpected details: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn bad_request_fields_keep_their_case() {
+        let field = Field::new(Property::Member {
+            name: "nickName".to_string(),
+        })
+        .with_context(Property::ArrayMember {
+            name: "children".to_string(),
+            index: 1,
+        });
+        let error = Error::invalid_argument("Invalid family").with_details(
+            ErrorDetails::bad_request(FieldViolation {
+                field: field.clone(),
+                description: None,
+            }),
+        );
+        let decoded = decode_status(&encode_status(&error)).expect("decoded status");
+        assert!(matches!(
+            decoded.inner().details.as_deref(),
+            Some([ErrorDetails::BadRequest { field_violations }])
+                if field_violations[0].field == field
+        ));
+    }
+
+    #[test]
+    fn all_details_round_trip() {
+        let error = Error::failed_precondition("Greeter is not ready")
+            .with_details(ErrorDetails::quota_failure(QuotaViolation {
+                subject: "project:greeter".to_string(),
+                description: Some("Daily limit exceeded".to_string()),
+            }))
+            .with_details(ErrorDetails::precondition_failure(PreconditionViolation {
+                r#type: "TOS".to_string(),
+                subject: "appbiotic.com/greeter".to_string(),
+                description: None,
+            }))
+            .with_details(ErrorDetails::RequestInfo {
+                request_id: "req-1".to_string(),
+                serving_data: Some("opaque".to_string()),
+            })
+            .with_details(ErrorDetails::ResourceInfo {
+                resource_type: "greeting".to_string(),
+                resource_name: "hello".to_string(),
+                owner: Some("user:kris".to_string()),
+                description: Some("Missing writer permission".to_string()),
+            })
+            .with_details(ErrorDetails::help(Link {
+                description: None,
+                url: "https://appbiotic.com/".to_string(),
+            }));
+
+        let bytes = encode_status(&error);
+        let decoded = decode_status(&bytes).expect("decoded status");
+        assert_eq!(decoded.code(), Code::FailedPrecondition);
+        assert_eq!(decoded.inner().details.as_ref().map(Vec::len), Some(5));
+        assert_eq!(encode_status(&decoded), bytes);
+    }
+
+    #[test]
+    fn decode_status_skips_unknown_details() {
+        let mut writer = Writer::default();
+        writer.int32(1, crate::code::INTERNAL);
+        let mut any = Writer::default();
+        any.string(1, Some("type.googleapis.com/appbiotic.Unknown"));
+        any.bytes(2, &[0x08, 0x01]);
+        writer.message(3, &any.into_bytes());
+
+        let error = decode_status(&writer.into_bytes()).expect("decoded status");
+        assert_eq!(error.code(), Code::Internal);
+        assert!(error.inner().details.is_none());
+    }
+
+    #[test]
+    fn decode_status_skips_malformed_details() {
+        let mut writer = Writer::default();
+        writer.int32(1, crate::code::UNAVAILABLE);
+        writer.string(2, Some("Greeter is down"));
+        let mut any = Writer::default();
+        any.string(1, Some("type.googleapis.com/google.rpc.ErrorInfo"));
+        any.bytes(2, &[0x0a, 0x05, b'x']);
+        writer.message(3, &any.into_bytes());
+        writer.message(
+            3,
+            &encode_any(&ErrorDetails::RequestInfo {
+                request_id: "req-1".to_string(),
+                serving_data: None,
+            }),
+        );
+
+        let error = decode_status(&writer.into_bytes()).expect("decoded status");
+        assert_eq!(error.code(), Code::Unavailable);
+        assert_eq!(error.inner().message.as_deref(), Some("Greeter is down"));
+        assert!(matches!(
+            error.inner().details.as_deref(),
+            Some([ErrorDetails::RequestInfo { request_id, .. }]) if request_id == "req-1"
+        ));
+    }
+
+    #[test]
+    fn decode_status_keeps_unparsed_fields() {
+        let mut bad_request = Writer::default();
+        for field in ["[0]", ""] {
+            let mut violation = Writer::default();
+            violation.string(1, Some(field));
+            violation.string(2, Some("Invalid"));
+            bad_request.message(1, &violation.into_bytes());
+        }
+        let mut any = Writer::default();
+        any.string(1, Some("type.googleapis.com/google.rpc.BadRequest"));
+        any.bytes(2, &bad_request.into_bytes());
+        let mut writer = Writer::default();
+        writer.int32(1, crate::code::INVALID_ARGUMENT);
+        writer.string(2, Some("Invalid greeting"));
+        writer.message(3, &any.into_bytes());
+
+        let bytes = writer.into_bytes();
+        let error = decode_status(&bytes).expect("decoded status");
+        assert_eq!(error.code(), Code::InvalidArgument);
+        assert_eq!(error.inner().message.as_deref(), Some("Invalid greeting"));
+        assert_eq!(encode_status(&error), bytes);
+        let fields: Vec<Field> = match error.inner().details.as_deref() {
+            Some([ErrorDetails::BadRequest { field_violations }]) => field_violations
+                .iter()
+                .map(|violation| violation.field.clone())
+                .collect(),
+            other => panic!("unexpected details: {other:?}"),
+        };
+        assert_eq!(fields, ["[0]", ""].map(Field::unparsed));
+    }
+
+    #[test]
+    fn decode_status_rejects_truncated_input() {
+        let bytes = from_hex(BAD_REQUEST_STATUS);
+        assert!(decode_status(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
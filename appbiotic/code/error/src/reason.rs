@@ -0,0 +1,365 @@
+//! Machine-readable error reasons, sent as the `ErrorDetails::ErrorInfo` of an
+//! [`Error`] so that clients can match on them instead of on messages.
+//!
+//! The reasons of a domain are declared by implementing [`Reason`], typically
+//! on an enum, and collected in a [`Registry`] to match decoded errors and to
+//! document them.
+//!
+//! ```
+//! use appbiotic_code_error::{
+//!     reason::{Reason, Registry},
+//!     Code,
+//! };
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum GreeterReason {
+//!     NameTooLong,
+//!     Closed,
+//! }
+//!
+//! impl Reason for GreeterReason {
+//!     const DOMAIN: &'static str = "greeter.appbiotic.com";
+//!     const ALL: &'static [Self] = &[GreeterReason::NameTooLong, GreeterReason::Closed];
+//!
+//!     fn reason(&self) -> &'static str {
+//!         match self {
+//!             GreeterReason::NameTooLong => "GREETING_NAME_TOO_LONG",
+//!             GreeterReason::Closed => "GREETER_CLOSED",
+//!         }
+//!     }
+//!
+//!     fn code(&self) -> Code {
+//!         match self {
+//!             GreeterReason::NameTooLong => Code::InvalidArgument,
+//!             GreeterReason::Closed => Code::FailedPrecondition,
+//!         }
+//!     }
+//!
+//!     fn message(&self) -> &'static str {
+//!         match self {
+//!             GreeterReason::NameTooLong => "The name is too long",
+//!             GreeterReason::Closed => "The greeter is closed",
+//!         }
+//!     }
+//! }
+//!
+//! let error = GreeterReason::Closed.error();
+//! assert_eq!(error.code(), Code::FailedPrecondition);
+//! assert_eq!(GreeterReason::of(&error), Some(&GreeterReason::Closed));
+//!
+//! let registry = Registry::new().with_domain::<GreeterReason>();
+//! assert_eq!(
+//!     registry.find(&error).map(|entry| entry.reason),
+//!     Some("GREETER_CLOSED")
+//! );
+//! ```
+
+use std::fmt::Write;
+
+use crate::{Code, Error, ErrorDetails};
+
+/// A reason of an error within a domain, with the canonical [`Code`] and
+/// default message of errors for it.
+pub trait Reason: Sized + 'static {
+    /// The logical grouping of the reasons, typically the registered service
+    /// name, e.g., `greeter.appbiotic.com`.
+    const DOMAIN: &'static str;
+
+    /// Every reason of the domain.
+    const ALL: &'static [Self];
+
+    /// The UPPER_SNAKE_CASE reason, unique within the domain, e.g.,
+    /// `GREETING_NAME_TOO_LONG`.
+    fn reason(&self) -> &'static str;
+
+    fn code(&self) -> Code;
+
+    fn message(&self) -> &'static str;
+
+    /// The keys of the metadata that errors for the reason carry.
+    fn metadata_keys(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Returns the `ErrorDetails::ErrorInfo` of the reason without metadata.
+    fn error_info(&self) -> ErrorDetails {
+        ErrorDetails::error_info(self.reason(), Self::DOMAIN)
+    }
+
+    /// Creates an error with the code, default message and `ErrorInfo` of the
+    /// reason.
+    fn error(&self) -> Error {
+        self.error_with_metadata::<&str, &str, _>([])
+    }
+
+    /// Creates an error like [`Reason::error`] with `metadata` in its
+    /// `ErrorInfo`. Every key should be one of the
+    /// [`metadata_keys`](Reason::metadata_keys).
+    fn error_with_metadata<K, V, I>(&self, metadata: I) -> Error
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let error_info =
+            metadata
+                .into_iter()
+                .fold(self.error_info(), |error_info, (key, value)| {
+                    debug_assert!(
+                        self.metadata_keys().contains(&key.as_ref()),
+                        "Undeclared metadata key of {}: {}",
+                        self.reason(),
+                        key.as_ref()
+                    );
+                    error_info.with_metadata(key, value)
+                });
+        Error::with_code(self.code(), self.message()).with_details(error_info)
+    }
+
+    /// Returns the reason of the domain that `error` carries, if any.
+    fn of(error: &Error) -> Option<&'static Self> {
+        error_infos(error)
+            .filter(|(domain, _)| *domain == Self::DOMAIN)
+            .find_map(|(_, reason)| Self::ALL.iter().find(|r| r.reason() == reason))
+    }
+}
+
+/// A reason registered in a [`Registry`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub domain: &'static str,
+    pub reason: &'static str,
+    pub code: Code,
+    pub message: &'static str,
+    pub metadata_keys: &'static [&'static str],
+}
+
+/// The reasons of one or more domains.
+#[derive(Clone, Debug, Default)]
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    /// Registers every reason of the domain of `R`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a reason is already registered in the domain or is not
+    /// UPPER_SNAKE_CASE of at most 63 characters.
+    pub fn with_domain<R: Reason>(mut self) -> Self {
+        for reason in R::ALL {
+            assert!(
+                is_valid_reason(reason.reason()),
+                "Invalid reason of {}: {}",
+                R::DOMAIN,
+                reason.reason()
+            );
+            assert!(
+                self.get(R::DOMAIN, reason.reason()).is_none(),
+                "Duplicate reason of {}: {}",
+                R::DOMAIN,
+                reason.reason()
+            );
+            self.entries.push(Entry {
+                domain: R::DOMAIN,
+                reason: reason.reason(),
+                code: reason.code(),
+                message: reason.message(),
+                metadata_keys: reason.metadata_keys(),
+            });
+        }
+        self
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn get(&self, domain: &str, reason: &str) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.domain == domain && entry.reason == reason)
+    }
+
+    /// Returns the first registered reason that `error` carries, e.g., after
+    /// decoding it from a response.
+    pub fn find(&self, error: &Error) -> Option<&Entry> {
+        error_infos(error).find_map(|(domain, reason)| self.get(domain, reason))
+    }
+
+    /// Renders the reasons as a markdown table, e.g., for API documentation.
+    ///
+    /// ```text
+    /// | Domain                | Reason                   | Code               | Message              | Metadata |
+    /// | :-------------------- | :----------------------- | :----------------- | :------------------- | :------- |
+    /// | greeter.appbiotic.com | `GREETING_NAME_TOO_LONG` | `INVALID_ARGUMENT` | The name is too long | `limit`  |
+    /// ```
+    pub fn to_markdown(&self) -> String {
+        let header = ["Domain", "Reason", "Code", "Message", "Metadata"].map(str::to_owned);
+        let rows: Vec<[String; 5]> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                [
+                    entry.domain.to_owned(),
+                    format!("`{}`", entry.reason),
+                    format!("`{}`", entry.code),
+                    entry.message.replace('|', "\\|"),
+                    entry
+                        .metadata_keys
+                        .iter()
+                        .map(|key| format!("`{key}`"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ]
+            })
+            .collect();
+        let mut widths = header.clone().map(|title| title.len());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let mut markdown = String::new();
+        let mut write_row = |cells: &[String; 5]| {
+            for (cell, width) in cells.iter().zip(widths) {
+                let _ = write!(markdown, "| {cell:width$} ");
+            }
+            markdown.push_str("|\n");
+        };
+        write_row(&header);
+        write_row(&widths.map(|width| format!(":{}", "-".repeat(width - 1))));
+        for row in &rows {
+            write_row(row);
+        }
+        markdown
+    }
+}
+
+fn error_infos(error: &Error) -> impl Iterator<Item = (&str, &str)> {
+    error
+        .inner()
+        .details
+        .iter()
+        .flatten()
+        .filter_map(|details| match details {
+            ErrorDetails::ErrorInfo { reason, domain, .. } => {
+                Some((domain.as_str(), reason.as_str()))
+            }
+            _ => None,
+        })
+}
+
+/// Whether `reason` matches `[A-Z][A-Z0-9_]+[A-Z0-9]` and has at most 63
+/// characters, as required by `google.rpc.ErrorInfo`.
+fn is_valid_reason(reason: &str) -> bool {
+    let bytes = reason.as_bytes();
+    (3..=63).contains(&bytes.len())
+        && bytes[0].is_ascii_uppercase()
+        && bytes[bytes.len() - 1] != b'_'
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || *b == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum GreeterReason {
+        NameTooLong,
+        Closed,
+    }
+
+    impl Reason for GreeterReason {
+        const DOMAIN: &'static str = "greeter.appbiotic.com";
+        const ALL: &'static [Self] = &[GreeterReason::NameTooLong, GreeterReason::Closed];
+
+        fn reason(&self) -> &'static str {
+            match self {
+                GreeterReason::NameTooLong => "GREETING_NAME_TOO_LONG",
+                GreeterReason::Closed => "GREETER_CLOSED",
+            }
+        }
+
+        fn code(&self) -> Code {
+            match self {
+                GreeterReason::NameTooLong => Code::InvalidArgument,
+                GreeterReason::Closed => Code::FailedPrecondition,
+            }
+        }
+
+        fn message(&self) -> &'static str {
+            match self {
+                GreeterReason::NameTooLong => "The name is too long",
+                GreeterReason::Closed => "The greeter is closed",
+            }
+        }
+
+        fn metadata_keys(&self) -> &'static [&'static str] {
+            match self {
+                GreeterReason::NameTooLong => &["limit", "length"],
+                GreeterReason::Closed => &[],
+            }
+        }
+    }
+
+    #[test]
+    fn reason_error() {
+        let error = GreeterReason::NameTooLong.error_with_metadata([("limit", "20")]);
+        assert_eq!(
+            error.to_string(),
+            "INVALID_ARGUMENT: The name is too long (error info: \
+             GREETING_NAME_TOO_LONG in greeter.appbiotic.com: limit=20)"
+        );
+
+        let decoded = crate::proto::decode_status(&crate::proto::encode_status(&error))
+            .expect("decoded status");
+        assert_eq!(
+            GreeterReason::of(&decoded),
+            Some(&GreeterReason::NameTooLong)
+        );
+        assert_eq!(GreeterReason::of(&Error::internal("Oops")), None);
+    }
+
+    #[test]
+    fn registry() {
+        let registry = Registry::new().with_domain::<GreeterReason>();
+        let entry = registry
+            .find(&GreeterReason::Closed.error())
+            .expect("registered reason");
+        assert_eq!(entry.code, Code::FailedPrecondition);
+        assert!(registry.get("greeter.appbiotic.com", "UNKNOWN").is_none());
+        assert_eq!(
+            registry.to_markdown(),
+            "\
+| Domain                | Reason                   | Code                  | Message               | Metadata          |
+| :-------------------- | :----------------------- | :-------------------- | :-------------------- | :---------------- |
+| greeter.appbiotic.com | `GREETING_NAME_TOO_LONG` | `INVALID_ARGUMENT`    | The name is too long  | `limit`, `length` |
+| greeter.appbiotic.com | `GREETER_CLOSED`         | `FAILED_PRECONDITION` | The greeter is closed |                   |
+"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate reason of greeter.appbiotic.com: GREETING_NAME_TOO_LONG")]
+    fn registry_rejects_duplicates() {
+        let _ = Registry::new()
+            .with_domain::<GreeterReason>()
+            .with_domain::<GreeterReason>();
+    }
+
+    #[test]
+    fn reason_validation() {
+        assert!(is_valid_reason("API_KEY_INVALID"));
+        assert!(!is_valid_reason("api_key_invalid"));
+        assert!(!is_valid_reason("API_KEY_"));
+        assert!(!is_valid_reason("_API_KEY"));
+    }
+}
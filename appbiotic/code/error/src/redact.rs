@@ -0,0 +1,223 @@
+//! Redaction of internal details before an [`Error`] is returned to clients.
+//!
+//! A [`Redactor`] turns an error into a client-safe one:
+//!
+//! - every `ErrorDetails::DebugInfo`, the source and the backtrace are dropped;
+//! - the message of [`Code::Internal`], [`Code::Unknown`] and
+//!   [`Code::DataLoss`] is replaced by generic text with a correlation id,
+//!   which is also attached as the `request_id` of a
+//!   `ErrorDetails::RequestInfo`;
+//! - all other details, e.g., `BadRequest` and `LocalizedMessage`, are kept.
+//!
+//! The original error is logged through `tracing` with the correlation id so
+//! the client-facing error can be matched with it.
+//!
+//! The default redactor is applied when an error is converted into a tonic
+//! status or an HTTP response, e.g., by `Error::into_tonic_status`. The `_with`
+//! variants of the conversions, e.g., `Error::into_tonic_status_with`, take
+//! the redactor to apply instead, such as [`Redactor::trusted`] to send the
+//! internal details to trusted clients, e.g., other internal services.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{event, Level};
+
+use crate::{Code, Error, ErrorDetails, ErrorStatus};
+
+/// The message of redacted internal errors.
+pub const INTERNAL_MESSAGE: &str = "An internal error occurred";
+
+type CorrelationIdFn = dyn Fn() -> String + Send + Sync;
+
+/// Turns errors into client-safe ones, see the [module](self) documentation.
+#[derive(Clone)]
+pub struct Redactor {
+    message: String,
+    correlation_id: Arc<CorrelationIdFn>,
+    trusted: bool,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Redactor {
+            message: INTERNAL_MESSAGE.to_owned(),
+            correlation_id: Arc::new(correlation_id),
+            trusted: false,
+        }
+    }
+}
+
+impl fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Redactor")
+            .field("message", &self.message)
+            .field("trusted", &self.trusted)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Redactor {
+    pub fn new() -> Self {
+        Redactor::default()
+    }
+
+    /// Creates a redactor for trusted clients that returns errors as they
+    /// are, with their internal messages, causes and debug info.
+    pub fn trusted() -> Self {
+        Redactor {
+            trusted: true,
+            ..Redactor::default()
+        }
+    }
+
+    /// Sets the generic message of redacted internal errors.
+    pub fn with_message<M: AsRef<str>>(self, message: M) -> Self {
+        Redactor {
+            message: message.as_ref().to_owned(),
+            ..self
+        }
+    }
+
+    /// Sets how correlation ids are generated, e.g., from the trace id of the
+    /// current request.
+    pub fn with_correlation_id<F>(self, correlation_id: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        Redactor {
+            correlation_id: Arc::new(correlation_id),
+            ..self
+        }
+    }
+
+    /// Returns a client-safe copy of `error`, logging the original. A
+    /// [trusted](Redactor::trusted) redactor returns `error` as it is.
+    pub fn redact(&self, error: Error) -> Error {
+        if self.trusted {
+            return error;
+        }
+        let is_internal = matches!(
+            error.code(),
+            Code::Internal | Code::Unknown | Code::DataLoss
+        );
+        let status = error.inner();
+        let mut details: Vec<ErrorDetails> = status
+            .details
+            .iter()
+            .flatten()
+            .filter(|details| !matches!(details, ErrorDetails::DebugInfo { .. }))
+            .cloned()
+            .collect();
+        let message = if is_internal {
+            let correlation_id = (self.correlation_id)();
+            event!(
+                Level::ERROR,
+                correlation_id,
+                details = ?status.encoded_details(),
+                "{error:#}"
+            );
+            details.push(ErrorDetails::request_info(&correlation_id));
+            Some(format!("{} (correlation id: {correlation_id})", self.message).into())
+        } else {
+            event!(
+                Level::DEBUG,
+                details = ?status.encoded_details(),
+                "{error:#}"
+            );
+            status.message.clone()
+        };
+        let status = ErrorStatus {
+            message,
+            details: (!details.is_empty()).then_some(details),
+            source: None,
+            backtrace: None,
+        };
+        error.map_inner(|_| status)
+    }
+}
+
+/// Generates a correlation id that is unique within the process and unlikely
+/// to repeat across processes.
+fn correlation_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{nanos:016x}-{:08x}-{count:x}", std::process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Field, FieldViolation, Property};
+
+    fn redactor() -> Redactor {
+        Redactor::new().with_correlation_id(|| "c0ffee".to_string())
+    }
+
+    #[test]
+    fn redact_internal_error() {
+        let error = Error::internal("Connection to db-3.internal refused")
+            .with_error("Connection refused")
+            .with_details(ErrorDetails::localized_message(
+                "fr-CA",
+                "Réessayez plus tard",
+            ))
+            .with_details(ErrorDetails::debug_info("pool exhausted"));
+
+        let error = redactor().redact(error);
+        assert_eq!(error.code(), Code::Internal);
+        assert_eq!(
+            error.to_string(),
+            "INTERNAL: An internal error occurred (correlation id: c0ffee) (\
+             localized message: [fr-CA] Réessayez plus tard; request info: c0ffee)"
+        );
+        assert!(error.inner().source.is_none());
+        assert!(error.inner().backtrace.is_none());
+    }
+
+    #[test]
+    fn redact_keeps_client_details() {
+        let error = Error::invalid_argument("Invalid greeting")
+            .with_details(ErrorDetails::bad_request(FieldViolation {
+                field: Field::new(Property::Member {
+                    name: "name".to_string(),
+                }),
+                description: Some("Too long".to_string()),
+            }))
+            .with_error("Name has 300 characters");
+
+        let error = redactor().with_message("Oops").redact(error);
+        assert_eq!(
+            error.to_string(),
+            "INVALID_ARGUMENT: Invalid greeting (bad request: name: Too long)"
+        );
+        assert_eq!(error.inner().encoded_details().len(), 1);
+    }
+
+    #[test]
+    fn trusted_redactor_keeps_internal_details() {
+        let error = Redactor::trusted().redact(
+            Error::internal("Connection to db-3.internal refused").with_error("Connection refused"),
+        );
+        assert_eq!(
+            error.to_string(),
+            "INTERNAL: Connection to db-3.internal refused"
+        );
+        assert!(error.inner().source.is_some());
+    }
+
+    #[test]
+    fn correlation_ids_are_unique() {
+        assert_ne!(correlation_id(), correlation_id());
+    }
+}
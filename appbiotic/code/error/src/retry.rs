@@ -0,0 +1,496 @@
+//! Retrying failed operations based on the error code, following the
+//! guidance of https://google.aip.dev/194 and the
+//! [API Design Guide](https://cloud.google.com/apis/design/errors#retrying_errors).
+//!
+//! | Code                 | Advice                                        |
+//! | :------------------- | :-------------------------------------------- |
+//! | `UNAVAILABLE`        | [`RetryAdvice::Retry`]                        |
+//! | `RESOURCE_EXHAUSTED` | [`RetryAdvice::Backoff`]                      |
+//! | `ABORTED`            | [`RetryAdvice::RetryHigherLevel`]             |
+//! | Any other code       | [`RetryAdvice::Never`]                        |
+//!
+//! A `ErrorDetails::RetryInfo` delay sent by the server is the lower bound of
+//! the exponential backoff of the [`RetryPolicy`]. The backoff is shortened by
+//! a random [`Jitter`], which can be seeded to make delays reproducible.
+
+use std::{
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Code, Error, ErrorDetails, Result};
+
+/// Whether and how a failed operation should be retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryAdvice {
+    /// Retry the failed call, after `delay` if the server asked for one.
+    Retry { delay: Option<Duration> },
+    /// Retry the failed call after backing off, e.g., to let a quota refill.
+    Backoff { delay: Option<Duration> },
+    /// Retry at a higher level, e.g., restart the whole read-modify-write
+    /// sequence rather than the failed write.
+    RetryHigherLevel { delay: Option<Duration> },
+    /// Do not retry until the cause of the error is fixed.
+    Never,
+}
+
+impl RetryAdvice {
+    /// Returns the delay the server asked for.
+    pub fn delay(&self) -> Option<Duration> {
+        match self {
+            RetryAdvice::Retry { delay }
+            | RetryAdvice::Backoff { delay }
+            | RetryAdvice::RetryHigherLevel { delay } => *delay,
+            RetryAdvice::Never => None,
+        }
+    }
+}
+
+impl Error {
+    /// Classifies the error for retrying, see the [`retry`](crate::retry)
+    /// module.
+    pub fn retry_advice(&self) -> RetryAdvice {
+        let delay = self
+            .inner()
+            .details
+            .iter()
+            .flatten()
+            .find_map(|details| match details {
+                ErrorDetails::RetryInfo { retry_delay } => *retry_delay,
+                _ => None,
+            });
+        match self.code() {
+            Code::Unavailable => RetryAdvice::Retry { delay },
+            Code::ResourceExhausted => RetryAdvice::Backoff { delay },
+            Code::Aborted => RetryAdvice::RetryHigherLevel { delay },
+            _ => RetryAdvice::Never,
+        }
+    }
+}
+
+/// Retries operations with exponential backoff and jitter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    jitter_seed: Option<u64>,
+    retry_higher_level: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            jitter_seed: None,
+            retry_higher_level: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+
+    /// Sets the number of attempts, including the first one.
+    pub fn with_max_attempts(self, max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            ..self
+        }
+    }
+
+    pub fn with_initial_backoff(self, initial_backoff: Duration) -> Self {
+        RetryPolicy {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    pub fn with_max_backoff(self, max_backoff: Duration) -> Self {
+        RetryPolicy {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Sets the factor the backoff grows by after each retry.
+    pub fn with_multiplier(self, multiplier: f64) -> Self {
+        RetryPolicy {
+            multiplier: multiplier.max(1.0),
+            ..self
+        }
+    }
+
+    /// Sets the fraction of the backoff that is randomly taken off to spread
+    /// out retries of concurrent clients, from `0.0` for none to `1.0`.
+    pub fn with_jitter(self, jitter: f64) -> Self {
+        RetryPolicy {
+            jitter: jitter.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Seeds the [`Jitter`] of [`RetryPolicy::delay`] and of each run of
+    /// [`RetryPolicy::retry`] so that the delays are reproducible, e.g., in
+    /// tests. By default the jitter is seeded by the time.
+    pub fn with_jitter_seed(self, seed: u64) -> Self {
+        RetryPolicy {
+            jitter_seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Also retries [`RetryAdvice::RetryHigherLevel`] errors, for operations
+    /// that are the higher level, e.g., a whole transaction.
+    pub fn with_retry_higher_level(self, retry_higher_level: bool) -> Self {
+        RetryPolicy {
+            retry_higher_level,
+            ..self
+        }
+    }
+
+    /// Returns the backoff before the `retry`-th retry, starting at `1`,
+    /// without jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::try_from_secs_f64(backoff)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// Returns the delay before the `retry`-th retry of a failed attempt, or
+    /// `None` if it should not be retried.
+    ///
+    /// The delay is the [`RetryPolicy::backoff`] shortened by the jitter, but
+    /// not shorter than the delay the server asked for.
+    pub fn delay(&self, retry: u32, error: &Error) -> Option<Duration> {
+        self.delay_with(retry, error, &mut self.new_jitter())
+    }
+
+    /// Returns the delay like [`RetryPolicy::delay`], drawing the jitter from
+    /// `jitter`.
+    pub fn delay_with(&self, retry: u32, error: &Error, jitter: &mut Jitter) -> Option<Duration> {
+        if retry >= self.max_attempts {
+            return None;
+        }
+        let advice = error.retry_advice();
+        match advice {
+            RetryAdvice::Never => return None,
+            RetryAdvice::RetryHigherLevel { .. } if !self.retry_higher_level => return None,
+            _ => {}
+        }
+        let backoff = self
+            .backoff(retry)
+            .mul_f64(1.0 - self.jitter * jitter.next_fraction());
+        Some(advice.delay().map_or(backoff, |delay| delay.max(backoff)))
+    }
+
+    fn new_jitter(&self) -> Jitter {
+        self.jitter_seed.map_or_else(Jitter::new, Jitter::seeded)
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error that should not
+    /// be retried or runs out of attempts, sleeping the current thread between
+    /// attempts.
+    pub fn retry<T, F>(&self, operation: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        self.retry_with_sleep(thread::sleep, operation)
+    }
+
+    /// Runs `operation` like [`RetryPolicy::retry`], waiting between attempts
+    /// with `sleep`, e.g., a fake clock in tests.
+    pub fn retry_with_sleep<T, S, F>(&self, mut sleep: S, mut operation: F) -> Result<T>
+    where
+        S: FnMut(Duration),
+        F: FnMut() -> Result<T>,
+    {
+        let mut jitter = self.new_jitter();
+        let mut retry = 1;
+        loop {
+            let error = match operation() {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            match self.delay_with(retry, &error, &mut jitter) {
+                Some(delay) => sleep(delay),
+                None => return Err(error),
+            }
+            retry += 1;
+        }
+    }
+
+    /// Runs the future returned by `operation` like [`RetryPolicy::retry`],
+    /// waiting between attempts on the future returned by `sleep`, e.g.,
+    /// `tokio::time::sleep`.
+    pub async fn retry_async<T, S, SF, F, OF>(&self, sleep: S, mut operation: F) -> Result<T>
+    where
+        S: Fn(Duration) -> SF,
+        SF: Future<Output = ()>,
+        F: FnMut() -> OF,
+        OF: Future<Output = Result<T>>,
+    {
+        let mut jitter = self.new_jitter();
+        let mut retry = 1;
+        loop {
+            let error = match operation().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            match self.delay_with(retry, &error, &mut jitter) {
+                Some(delay) => sleep(delay).await,
+                None => return Err(error),
+            }
+            retry += 1;
+        }
+    }
+}
+
+/// The pseudo-random source of the jitter of retry delays, using SplitMix64.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Jitter {
+    state: u64,
+}
+
+impl Default for Jitter {
+    /// Seeds the jitter by the time and a counter, so that concurrent clients
+    /// spread out their retries.
+    fn default() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        Jitter::seeded(
+            COUNTER
+                .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+                .wrapping_add(u64::from(nanos)),
+        )
+    }
+}
+
+impl Jitter {
+    pub fn new() -> Self {
+        Jitter::default()
+    }
+
+    /// Creates a jitter that always draws the same sequence for `seed`.
+    pub fn seeded(seed: u64) -> Self {
+        Jitter { state: seed }
+    }
+
+    /// Returns the next pseudo-random number in `[0, 1)`.
+    pub fn next_fraction(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        pin::pin,
+        sync::Arc,
+        task::{Context, Poll, Wake, Waker},
+    };
+
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new()
+            .with_max_attempts(4)
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(250))
+            .with_jitter(0.0)
+    }
+
+    #[test]
+    fn retry_advice() {
+        assert_eq!(
+            Error::unavailable("Try later").retry_advice(),
+            RetryAdvice::Retry { delay: None }
+        );
+        assert_eq!(
+            Error::resource_exhausted("Quota exceeded")
+                .with_details(ErrorDetails::retry_info(Duration::from_secs(3)))
+                .retry_advice(),
+            RetryAdvice::Backoff {
+                delay: Some(Duration::from_secs(3))
+            }
+        );
+        assert_eq!(
+            Error::aborted("Conflict").retry_advice(),
+            RetryAdvice::RetryHigherLevel { delay: None }
+        );
+        assert_eq!(
+            Error::failed_precondition("Not empty").retry_advice(),
+            RetryAdvice::Never
+        );
+    }
+
+    #[test]
+    fn backoff_grows_up_to_max() {
+        let policy = policy();
+        let backoffs: Vec<u128> = (1..=4)
+            .map(|retry| policy.backoff(retry).as_millis())
+            .collect();
+        assert_eq!(backoffs, [100, 200, 250, 250]);
+    }
+
+    #[test]
+    fn jitter_shortens_backoff() {
+        let policy = policy().with_jitter(0.5);
+        let error = Error::unavailable("Try later");
+        for _ in 0..100 {
+            let delay = policy.delay(2, &error).expect("retry delay");
+            assert!(delay > Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn seeded_jitter_is_reproducible() {
+        let policy = policy().with_jitter(0.5).with_jitter_seed(7);
+        let run = || {
+            let mut sleeps = Vec::new();
+            let _: Result<()> = policy.retry_with_sleep(
+                |delay| sleeps.push(delay),
+                || Err(Error::unavailable("Try later")),
+            );
+            sleeps
+        };
+        let sleeps = run();
+        assert_eq!(sleeps.len(), 3);
+        assert_eq!(sleeps, run());
+        let mut jitter = Jitter::seeded(7);
+        let expected: Vec<Duration> = (1..=3)
+            .map(|retry| {
+                policy
+                    .backoff(retry)
+                    .mul_f64(1.0 - 0.5 * jitter.next_fraction())
+            })
+            .collect();
+        assert_eq!(sleeps, expected);
+    }
+
+    #[test]
+    fn server_delay_is_lower_bound() {
+        let policy = policy();
+        let error = Error::unavailable("Try later")
+            .with_details(ErrorDetails::retry_info(Duration::from_millis(50)));
+        assert_eq!(policy.delay(2, &error), Some(Duration::from_millis(200)));
+        let error = Error::unavailable("Try later")
+            .with_details(ErrorDetails::retry_info(Duration::from_secs(2)));
+        assert_eq!(policy.delay(2, &error), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn retry_until_success() {
+        let sleeps = RefCell::new(Vec::new());
+        let mut attempts = 0;
+        let result = policy().retry_with_sleep(
+            |delay| sleeps.borrow_mut().push(delay),
+            || {
+                attempts += 1;
+                match attempts {
+                    1 => Err(Error::unavailable("Try later")),
+                    2 => Err(Error::resource_exhausted("Slow down")
+                        .with_details(ErrorDetails::retry_info(Duration::from_secs(2)))),
+                    _ => Ok(attempts),
+                }
+            },
+        );
+        assert_eq!(result.expect("success"), 3);
+        assert_eq!(
+            sleeps.into_inner(),
+            [Duration::from_millis(100), Duration::from_secs(2)]
+        );
+    }
+
+    #[test]
+    fn retry_stops_on_budget_and_non_retryable_errors() {
+        let mut sleeps = 0;
+        let mut attempts = 0;
+        let result: Result<()> = policy().retry_with_sleep(
+            |_| sleeps += 1,
+            || {
+                attempts += 1;
+                Err(Error::unavailable("Try later"))
+            },
+        );
+        assert!(matches!(result, Err(error) if error.code() == Code::Unavailable));
+        assert_eq!((attempts, sleeps), (4, 3));
+
+        let mut attempts = 0;
+        let result: Result<()> = policy().retry_with_sleep(
+            |_| {},
+            || {
+                attempts += 1;
+                Err(Error::aborted("Conflict"))
+            },
+        );
+        assert!(matches!(result, Err(error) if error.code() == Code::Aborted));
+        assert_eq!(attempts, 1);
+    }
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut context = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn retry_async() {
+        let sleeps = RefCell::new(Vec::new());
+        let attempts = RefCell::new(0);
+        let result = block_on(policy().with_retry_higher_level(true).retry_async(
+            |delay| {
+                sleeps.borrow_mut().push(delay);
+                std::future::ready(())
+            },
+            || {
+                *attempts.borrow_mut() += 1;
+                let attempt = *attempts.borrow();
+                async move {
+                    if attempt < 3 {
+                        Err(Error::aborted("Conflict"))
+                    } else {
+                        Ok("committed")
+                    }
+                }
+            },
+        ));
+        assert_eq!(result.expect("success"), "committed");
+        assert_eq!(
+            sleeps.into_inner(),
+            [Duration::from_millis(100), Duration::from_millis(200)]
+        );
+    }
+}
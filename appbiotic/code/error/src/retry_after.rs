@@ -0,0 +1,86 @@
+//! Parsing of the HTTP `Retry-After` header, see
+//! https://www.rfc-editor.org/rfc/rfc9110#name-retry-after.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parses a `Retry-After` value given as either a number of seconds or an
+/// HTTP date relative to `now`. Dates in the past yield a zero delay.
+///
+/// Only the preferred `IMF-fixdate` format of HTTP dates is supported, e.g.,
+/// `Sun, 06 Nov 1994 08:49:37 GMT`.
+pub(crate) fn parse(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+        return value.parse().ok().map(Duration::from_secs);
+    }
+    let date = parse_imf_fixdate(value)?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+fn parse_imf_fixdate(value: &str) -> Option<SystemTime> {
+    let (_day_name, rest) = value.split_once(", ")?;
+    let parts: Vec<&str> = rest.split(' ').collect();
+    let [day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+    let day: i64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|m| m == month)? as i64 + 1;
+    let year: i64 = year.parse().ok()?;
+    let mut time = time.split(':').map(|part| part.parse::<i64>().ok());
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+    if time.next().is_some()
+        || !(1..=31).contains(&day)
+        || !(0..24).contains(&hours)
+        || !(0..60).contains(&minutes)
+        || !(0..61).contains(&seconds)
+    {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    let seconds = days * 86_400 + hours * 3_600 + minutes * 60 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(seconds).ok()?))
+}
+
+/// Returns the number of days since 1970-01-01 for a proleptic Gregorian
+/// calendar date, see https://howardhinnant.github.io/date_algorithms.html.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_delay_seconds() {
+        assert_eq!(parse("120", UNIX_EPOCH), Some(Duration::from_secs(120)));
+        assert_eq!(parse(" 0 ", UNIX_EPOCH), Some(Duration::ZERO));
+        assert_eq!(parse("-1", UNIX_EPOCH), None);
+    }
+
+    #[test]
+    fn parse_http_date() {
+        // 1994-11-06T08:49:37Z
+        let date = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        let value = "Sun, 06 Nov 1994 08:49:37 GMT";
+        assert_eq!(
+            parse(value, date - Duration::from_secs(90)),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse(value, date + Duration::from_secs(90)),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse("Sunday, 06-Nov-94 08:49:37 GMT", date), None);
+    }
+}
@@ -0,0 +1,670 @@
+//! Serialization of [`Error`], [`ErrorStatus`] and [`ErrorDetails`] following
+//! the JSON mapping of
+//! https://github.com/googleapis/googleapis/blob/f36c65081b19e0758ef5696feca27c7dcee5475e/google/rpc/status.proto.
+//!
+//! The error types are converted into private representations mirroring the
+//! protobuf messages so the public types are free of serialization concerns.
+//!
+//! Details of an unknown `@type` are skipped when deserializing a status, as
+//! they are when decoding one from protobuf.
+
+use std::{borrow::Cow, collections::BTreeMap, str::FromStr, time::Duration};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    Error, ErrorDetails, ErrorStatus, Field, FieldViolation, Link, PreconditionViolation,
+    QuotaViolation,
+};
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StatusRepr {
+            code: self.code().value(),
+            status: ErrorStatusRepr::from(self.inner()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Error {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = StatusRepr::deserialize(deserializer)?;
+        let status = ErrorStatus::try_from(repr.status).map_err(de::Error::custom)?;
+        Error::from_code(repr.code, status).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ErrorStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorStatusRepr::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ErrorStatusRepr::deserialize(deserializer)?;
+        ErrorStatus::try_from(repr).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ErrorDetails {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        DetailsRepr::from(self.clone()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorDetails {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = DetailsRepr::deserialize(deserializer)?;
+        ErrorDetails::try_from(repr).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StatusRepr {
+    #[serde(default)]
+    code: i32,
+    #[serde(flatten)]
+    status: ErrorStatusRepr,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ErrorStatusRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    details: Vec<DetailsRepr>,
+}
+
+impl From<&ErrorStatus> for ErrorStatusRepr {
+    fn from(value: &ErrorStatus) -> Self {
+        ErrorStatusRepr {
+            message: value.message.as_deref().map(str::to_owned),
+            details: value
+                .encoded_details()
+                .into_iter()
+                .map(DetailsRepr::from)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<ErrorStatusRepr> for ErrorStatus {
+    type Error = Error;
+
+    fn try_from(value: ErrorStatusRepr) -> Result<Self, Self::Error> {
+        let details = value
+            .details
+            .into_iter()
+            .filter(|details| !matches!(details, DetailsRepr::Unknown))
+            .map(ErrorDetails::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ErrorStatus {
+            message: value.message.map(Cow::from),
+            details: (!details.is_empty()).then_some(details),
+            source: None,
+            backtrace: None,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "@type")]
+enum DetailsRepr {
+    #[serde(rename = "type.googleapis.com/google.rpc.ErrorInfo")]
+    ErrorInfo(ErrorInfoRepr),
+    #[serde(rename = "type.googleapis.com/google.rpc.RetryInfo")]
+    RetryInfo(RetryInfoRepr),
+    #[serde(rename = "type.googleapis.com/google.rpc.QuotaFailure")]
+    QuotaFailure(QuotaFailureRepr),
+    #[serde(rename = "type.googleapis.com/google.rpc.PreconditionFailure")]
+    PreconditionFailure(PreconditionFailureRepr),
+    #[serde(rename = "type.googleapis.com/google.rpc.BadRequest")]
+    BadRequest(BadRequestRepr),
+    #[serde(rename = "type.googleapis.com/google.rpc.RequestInfo")]
+    RequestInfo(RequestInfoRepr),
+    #[serde(rename = "type.googleapis.com/google.rpc.ResourceInfo")]
+    ResourceInfo(ResourceInfoRepr),
+    #[serde(rename = "type.googleapis.com/google.rpc.Help")]
+    Help(HelpRepr),
+    #[serde(rename = "type.googleapis.com/google.rpc.DebugInfo")]
+    DebugInfo(DebugInfoRepr),
+    #[serde(rename = "type.googleapis.com/google.rpc.LocalizedMessage")]
+    LocalizedMessage(LocalizedMessageRepr),
+    /// Details of a type that is not one of the error details, e.g., a
+    /// service's own message, which is never serialized.
+    #[serde(other, skip_serializing)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ErrorInfoRepr {
+    #[serde(default)]
+    reason: String,
+    #[serde(default)]
+    domain: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RetryInfoRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "duration")]
+    retry_delay: Option<Duration>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuotaFailureRepr {
+    #[serde(default)]
+    violations: Vec<QuotaViolationRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QuotaViolationRepr {
+    #[serde(default)]
+    subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreconditionFailureRepr {
+    #[serde(default)]
+    violations: Vec<PreconditionViolationRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PreconditionViolationRepr {
+    #[serde(default)]
+    r#type: String,
+    #[serde(default)]
+    subject: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BadRequestRepr {
+    #[serde(default)]
+    field_violations: Vec<FieldViolationRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FieldViolationRepr {
+    #[serde(default)]
+    field: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestInfoRepr {
+    #[serde(default)]
+    request_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    serving_data: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceInfoRepr {
+    #[serde(default)]
+    resource_type: String,
+    #[serde(default)]
+    resource_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HelpRepr {
+    #[serde(default)]
+    links: Vec<LinkRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default)]
+    url: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DebugInfoRepr {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stack_entries: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalizedMessageRepr {
+    #[serde(default)]
+    locale: String,
+    #[serde(default)]
+    message: String,
+}
+
+impl From<ErrorDetails> for DetailsRepr {
+    fn from(value: ErrorDetails) -> Self {
+        match value {
+            ErrorDetails::ErrorInfo {
+                reason,
+                domain,
+                metadata,
+            } => DetailsRepr::ErrorInfo(ErrorInfoRepr {
+                reason,
+                domain,
+                metadata,
+            }),
+            ErrorDetails::RetryInfo { retry_delay } => {
+                DetailsRepr::RetryInfo(RetryInfoRepr { retry_delay })
+            }
+            ErrorDetails::QuotaFailure { violations } => {
+                DetailsRepr::QuotaFailure(QuotaFailureRepr {
+                    violations: violations
+                        .into_iter()
+                        .map(|violation| QuotaViolationRepr {
+                            subject: violation.subject,
+                            description: violation.description,
+                        })
+                        .collect(),
+                })
+            }
+            ErrorDetails::PreconditionFailure { violations } => {
+                DetailsRepr::PreconditionFailure(PreconditionFailureRepr {
+                    violations: violations
+                        .into_iter()
+                        .map(|violation| PreconditionViolationRepr {
+                            r#type: violation.r#type,
+                            subject: violation.subject,
+                            description: violation.description,
+                        })
+                        .collect(),
+                })
+            }
+            ErrorDetails::BadRequest { field_violations } => {
+                DetailsRepr::BadRequest(BadRequestRepr {
+                    field_violations: field_violations
+                        .into_iter()
+                        .map(|violation| FieldViolationRepr {
+                            field: violation.field.to_string(),
+                            description: violation.description,
+                        })
+                        .collect(),
+                })
+            }
+            ErrorDetails::RequestInfo {
+                request_id,
+                serving_data,
+            } => DetailsRepr::RequestInfo(RequestInfoRepr {
+                request_id,
+                serving_data,
+            }),
+            ErrorDetails::ResourceInfo {
+                resource_type,
+                resource_name,
+                owner,
+                description,
+            } => DetailsRepr::ResourceInfo(ResourceInfoRepr {
+                resource_type,
+                resource_name,
+                owner,
+                description,
+            }),
+            ErrorDetails::Help { links } => DetailsRepr::Help(HelpRepr {
+                links: links
+                    .into_iter()
+                    .map(|link| LinkRepr {
+                        description: link.description,
+                        url: link.url,
+                    })
+                    .collect(),
+            }),
+            ErrorDetails::DebugInfo {
+                stack_entries,
+                detail,
+            } => DetailsRepr::DebugInfo(DebugInfoRepr {
+                stack_entries,
+                detail,
+            }),
+            ErrorDetails::LocalizedMessage { locale, message } => {
+                DetailsRepr::LocalizedMessage(LocalizedMessageRepr { locale, message })
+            }
+        }
+    }
+}
+
+impl TryFrom<DetailsRepr> for ErrorDetails {
+    type Error = Error;
+
+    fn try_from(value: DetailsRepr) -> Result<Self, Self::Error> {
+        Ok(match value {
+            DetailsRepr::ErrorInfo(repr) => ErrorDetails::ErrorInfo {
+                reason: repr.reason,
+                domain: repr.domain,
+                metadata: repr.metadata,
+            },
+            DetailsRepr::RetryInfo(repr) => ErrorDetails::RetryInfo {
+                retry_delay: repr.retry_delay,
+            },
+            DetailsRepr::QuotaFailure(repr) => ErrorDetails::QuotaFailure {
+                violations: repr
+                    .violations
+                    .into_iter()
+                    .map(|violation| QuotaViolation {
+                        subject: violation.subject,
+                        description: violation.description,
+                    })
+                    .collect(),
+            },
+            DetailsRepr::PreconditionFailure(repr) => ErrorDetails::PreconditionFailure {
+                violations: repr
+                    .violations
+                    .into_iter()
+                    .map(|violation| PreconditionViolation {
+                        r#type: violation.r#type,
+                        subject: violation.subject,
+                        description: violation.description,
+                    })
+                    .collect(),
+            },
+            DetailsRepr::BadRequest(repr) => ErrorDetails::BadRequest {
+                field_violations: repr
+                    .field_violations
+                    .into_iter()
+                    .map(|violation| FieldViolation {
+                        field: Field::from_str(&violation.field)
+                            .unwrap_or_else(|_| Field::unparsed(violation.field)),
+                        description: violation.description,
+                    })
+                    .collect(),
+            },
+            DetailsRepr::RequestInfo(repr) => ErrorDetails::RequestInfo {
+                request_id: repr.request_id,
+                serving_data: repr.serving_data,
+            },
+            DetailsRepr::ResourceInfo(repr) => ErrorDetails::ResourceInfo {
+                resource_type: repr.resource_type,
+                resource_name: repr.resource_name,
+                owner: repr.owner,
+                description: repr.description,
+            },
+            DetailsRepr::Help(repr) => ErrorDetails::Help {
+                links: repr
+                    .links
+                    .into_iter()
+                    .map(|link| Link {
+                        description: link.description,
+                        url: link.url,
+                    })
+                    .collect(),
+            },
+            DetailsRepr::DebugInfo(repr) => ErrorDetails::DebugInfo {
+                stack_entries: repr.stack_entries,
+                detail: repr.detail,
+            },
+            DetailsRepr::LocalizedMessage(repr) => ErrorDetails::LocalizedMessage {
+                locale: repr.locale,
+                message: repr.message,
+            },
+            DetailsRepr::Unknown => {
+                return Err(Error::invalid_argument("Unknown type of error details"))
+            }
+        })
+    }
+}
+
+/// Serializes a `google.protobuf.Duration` as its JSON string form, e.g.,
+/// `"1.500s"`.
+mod duration {
+    use std::time::Duration;
+
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(duration) => serializer.serialize_str(&format(duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        let value = Option::<String>::deserialize(deserializer)?;
+        value
+            .map(|value| {
+                parse(&value).ok_or_else(|| {
+                    de::Error::invalid_value(
+                        de::Unexpected::Str(&value),
+                        &"a duration like \"1.5s\"",
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// Formats with 0, 3, 6, or 9 fractional digits as required by the
+    /// protobuf JSON mapping.
+    fn format(duration: &Duration) -> String {
+        let seconds = duration.as_secs();
+        let nanos = duration.subsec_nanos();
+        if nanos == 0 {
+            format!("{seconds}s")
+        } else if nanos.is_multiple_of(1_000_000) {
+            format!("{seconds}.{:03}s", nanos / 1_000_000)
+        } else if nanos.is_multiple_of(1_000) {
+            format!("{seconds}.{:06}s", nanos / 1_000)
+        } else {
+            format!("{seconds}.{nanos:09}s")
+        }
+    }
+
+    fn parse(value: &str) -> Option<Duration> {
+        let value = value.strip_suffix('s')?;
+        let (seconds, fraction) = value.split_once('.').unwrap_or((value, ""));
+        if fraction.len() > 9 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let seconds = seconds.parse().ok()?;
+        let nanos = if fraction.is_empty() {
+            0
+        } else {
+            format!("{fraction:0<9}").parse().ok()?
+        };
+        Some(Duration::new(seconds, nanos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::{Code, Error, ErrorDetails, Field, FieldViolation, Property};
+
+    #[test]
+    fn error_serializes_as_google_rpc_status() {
+        let error = Error::invalid_argument("Invalid greeting")
+            .with_details(ErrorDetails::bad_request(FieldViolation {
+                field: Field::new(Property::MapMember {
+                    name: "nicknames".to_string(),
+                    key: "joe".to_string(),
+                })
+                .with_context(Property::ArrayMember {
+                    name: "children".to_string(),
+                    index: 3,
+                }),
+                description: Some("Nickname is too long".to_string()),
+            }))
+            .with_details(ErrorDetails::retry_info(Duration::from_millis(1500)))
+            .with_details(
+                ErrorDetails::error_info("NAME_TOO_LONG", "greeter.appbiotic.com")
+                    .with_metadata("maxLength", "20"),
+            );
+
+        let value = serde_json::to_value(&error).expect("serialized error");
+        assert_eq!(
+            value,
+            json!({
+                "code": 3,
+                "message": "Invalid greeting",
+                "details": [
+                    {
+                        "@type": "type.googleapis.com/google.rpc.BadRequest",
+                        "fieldViolations": [
+                            {
+                                "field": "children[3].nicknames[\"joe\"]",
+                                "description": "Nickname is too long",
+                            },
+                        ],
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.rpc.RetryInfo",
+                        "retryDelay": "1.500s",
+                    },
+                    {
+                        "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                        "reason": "NAME_TOO_LONG",
+                        "domain": "greeter.appbiotic.com",
+                        "metadata": { "maxLength": "20" },
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn error_deserializes_from_google_rpc_status() {
+        let value = json!({
+            "code": 5,
+            "message": "Greeting not found",
+            "details": [
+                {
+                    "@type": "type.googleapis.com/google.rpc.BadRequest",
+                    "fieldViolations": [
+                        { "field": "family.children[3].nicknames[\"joe\"]" },
+                    ],
+                },
+                {
+                    "@type": "type.googleapis.com/google.rpc.LocalizedMessage",
+                    "locale": "fr-CA",
+                    "message": "Salutation introuvable",
+                },
+                {
+                    "@type": "type.googleapis.com/google.rpc.RetryInfo",
+                    "retryDelay": "0.000250s",
+                },
+            ],
+        });
+
+        let error: Error = serde_json::from_value(value.clone()).expect("deserialized error");
+        assert_eq!(error.code(), Code::NotFound);
+        assert_eq!(error.inner().message.as_deref(), Some("Greeting not found"));
+        let details = error.inner().details.as_ref().expect("some error details");
+        match &details[0] {
+            ErrorDetails::BadRequest { field_violations } => {
+                assert_eq!(
+                    field_violations[0].field.to_string(),
+                    r#"family.children[3].nicknames["joe"]"#
+                );
+            }
+            other => panic!("unexpected details: {other:?}"),
+        }
+        assert!(matches!(
+            details[2],
+            ErrorDetails::RetryInfo {
+                retry_delay: Some(delay)
+            } if delay == Duration::from_micros(250)
+        ));
+        assert_eq!(
+            serde_json::to_value(&error).expect("serialized error"),
+            value
+        );
+    }
+
+    #[test]
+    fn error_deserialize_skips_unknown_details() {
+        let error: Error = serde_json::from_value(json!({
+            "code": 9,
+            "message": "Greeter closed",
+            "details": [
+                {
+                    "@type": "type.googleapis.com/greeter.v1.Schedule",
+                    "opensAt": "09:00",
+                },
+                {
+                    "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                    "reason": "GREETER_CLOSED",
+                    "domain": "greeter.appbiotic.com",
+                },
+            ],
+        }))
+        .expect("deserialized error");
+        assert_eq!(error.code(), Code::FailedPrecondition);
+        let details = error.inner().details.as_ref().expect("some error details");
+        assert_eq!(details.len(), 1);
+        assert!(
+            matches!(&details[0], ErrorDetails::ErrorInfo { reason, .. } if reason == "GREETER_CLOSED")
+        );
+        assert!(serde_json::from_value::<ErrorDetails>(json!({
+            "@type": "type.googleapis.com/greeter.v1.Schedule",
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn error_keeps_unparsed_field_paths() {
+        let value = json!({
+            "code": 3,
+            "message": "Invalid greeting",
+            "details": [
+                {
+                    "@type": "type.googleapis.com/google.rpc.BadRequest",
+                    "fieldViolations": [
+                        { "field": "[0]", "description": "Too long" },
+                        { "field": "recipients..name" },
+                    ],
+                },
+            ],
+        });
+        let error: Error = serde_json::from_value(value.clone()).expect("deserialized error");
+        assert!(matches!(
+            error.inner().details.as_deref(),
+            Some([ErrorDetails::BadRequest { field_violations }])
+                if field_violations.iter().all(|violation| violation.field.is_unparsed())
+        ));
+        assert_eq!(
+            serde_json::to_value(&error).expect("serialized error"),
+            value
+        );
+    }
+
+    #[test]
+    fn error_deserialize_rejects_ok_code() {
+        let result = serde_json::from_value::<Error>(json!({ "code": 0 }));
+        assert!(result.is_err());
+    }
+}
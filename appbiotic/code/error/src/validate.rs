@@ -0,0 +1,551 @@
+//! Validation of requests that collects every [`FieldViolation`] into a
+//! single [`Code::InvalidArgument`] error.
+//!
+//! ```
+//! use appbiotic_code_error::validate::{Validate, Validator};
+//!
+//! struct Child {
+//!     name: String,
+//!     age: u32,
+//! }
+//!
+//! struct Family {
+//!     children: Vec<Child>,
+//! }
+//!
+//! impl Validate for Child {
+//!     fn validate(&self, validator: &mut Validator) {
+//!         validator.field("name", &self.name).required().length(..=20);
+//!         validator.field("age", &self.age).range(..18);
+//!     }
+//! }
+//!
+//! impl Validate for Family {
+//!     fn validate(&self, validator: &mut Validator) {
+//!         validator.array("children", &self.children);
+//!     }
+//! }
+//!
+//! let family = Family {
+//!     children: vec![Child { name: String::new(), age: 21 }],
+//! };
+//! let error = Validator::validate(&family).unwrap_err();
+//! assert_eq!(
+//!     error.to_string(),
+//!     "INVALID_ARGUMENT: Invalid request (bad request: \
+//!      children[0].name: Required, children[0].age: Must be less than 18)"
+//! );
+//! ```
+
+use std::{
+    fmt,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::{Code, Error, ErrorDetails, ErrorStatus, Field, FieldViolation, Property, Result};
+
+/// A type whose members can be validated.
+pub trait Validate {
+    /// Checks the members, recording any violations in `validator`.
+    fn validate(&self, validator: &mut Validator);
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate(&self, validator: &mut Validator) {
+        if let Some(value) = self {
+            value.validate(validator);
+        }
+    }
+}
+
+impl<T: Validate + ?Sized> Validate for Box<T> {
+    fn validate(&self, validator: &mut Validator) {
+        self.as_ref().validate(validator);
+    }
+}
+
+/// Accumulates the field violations of a request while descending into its
+/// members.
+#[derive(Debug, Default)]
+pub struct Validator {
+    /// The properties of the member being validated, from the root.
+    context: Vec<Property>,
+    field_violations: Vec<FieldViolation>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Validator::default()
+    }
+
+    /// Validates `value`, failing with all of its violations.
+    pub fn validate<T: Validate + ?Sized>(value: &T) -> Result<()> {
+        let mut validator = Validator::new();
+        value.validate(&mut validator);
+        validator.finish()
+    }
+
+    /// Validates the member `name`.
+    pub fn member<T: Validate + ?Sized>(&mut self, name: &str, value: &T) -> &mut Self {
+        self.within(
+            Property::Member {
+                name: name.to_owned(),
+            },
+            value,
+        )
+    }
+
+    /// Validates each element of the array member `name`.
+    pub fn array<T: Validate>(&mut self, name: &str, values: &[T]) -> &mut Self {
+        for (index, value) in values.iter().enumerate() {
+            self.within(
+                Property::ArrayMember {
+                    name: name.to_owned(),
+                    index,
+                },
+                value,
+            );
+        }
+        self
+    }
+
+    /// Validates each value of the map member `name`.
+    pub fn map<'v, K, T, I>(&mut self, name: &str, entries: I) -> &mut Self
+    where
+        K: fmt::Display,
+        T: Validate + 'v,
+        I: IntoIterator<Item = (K, &'v T)>,
+    {
+        for (key, value) in entries {
+            self.within(
+                Property::MapMember {
+                    name: name.to_owned(),
+                    key: key.to_string(),
+                },
+                value,
+            );
+        }
+        self
+    }
+
+    /// Validates `value` at `property` of the current member.
+    pub fn within<T: Validate + ?Sized>(&mut self, property: Property, value: &T) -> &mut Self {
+        self.context.push(property);
+        value.validate(self);
+        self.context.pop();
+        self
+    }
+
+    /// Starts checking the rules of the member `name`.
+    pub fn field<'a, T: ?Sized>(&'a mut self, name: &str, value: &'a T) -> FieldCheck<'a, T> {
+        self.check(
+            Property::Member {
+                name: name.to_owned(),
+            },
+            value,
+        )
+    }
+
+    /// Starts checking the rules of `value` at `property` of the current
+    /// member, e.g., an element of an array of strings.
+    pub fn check<'a, T: ?Sized>(
+        &'a mut self,
+        property: Property,
+        value: &'a T,
+    ) -> FieldCheck<'a, T> {
+        FieldCheck {
+            validator: self,
+            property,
+            value,
+        }
+    }
+
+    /// Checks that exactly one member of a `oneof` group named `name` is set,
+    /// given each member's name and whether it is set.
+    pub fn one_of(&mut self, name: &str, members: &[(&str, bool)]) -> &mut Self {
+        if members.iter().filter(|(_, is_set)| *is_set).count() != 1 {
+            let names: Vec<&str> = members.iter().map(|(name, _)| *name).collect();
+            self.violation(
+                Property::Member {
+                    name: name.to_owned(),
+                },
+                format!("Exactly one of {} must be set", names.join(", ")),
+            );
+        }
+        self
+    }
+
+    /// Records a violation at `property` of the current member.
+    pub fn violation<D: AsRef<str>>(&mut self, property: Property, description: D) -> &mut Self {
+        let field = self
+            .context
+            .iter()
+            .rev()
+            .fold(Field::new(property), |field, context| {
+                field.with_context(context.clone())
+            });
+        self.field_violations.push(FieldViolation {
+            field,
+            description: Some(description.as_ref().to_owned()),
+        });
+        self
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.field_violations.is_empty()
+    }
+
+    pub fn field_violations(&self) -> &[FieldViolation] {
+        &self.field_violations
+    }
+
+    /// Fails with a [`Code::InvalidArgument`] error carrying one
+    /// `ErrorDetails::BadRequest` with every recorded violation.
+    pub fn finish(self) -> Result<()> {
+        if self.field_violations.is_empty() {
+            return Ok(());
+        }
+        Err(Error::new(
+            Code::InvalidArgument,
+            ErrorStatus::default()
+                .with_message("Invalid request")
+                .with_details(ErrorDetails::BadRequest {
+                    field_violations: self.field_violations,
+                }),
+        ))
+    }
+}
+
+/// Checks built-in rules against a value, recording a violation for each
+/// rule that fails.
+pub struct FieldCheck<'a, T: ?Sized> {
+    validator: &'a mut Validator,
+    property: Property,
+    value: &'a T,
+}
+
+impl<'a, T: ?Sized> FieldCheck<'a, T> {
+    /// Records a violation with `description` unless `is_valid` holds.
+    pub fn rule<D: AsRef<str>>(self, is_valid: bool, description: D) -> Self {
+        if !is_valid {
+            self.validator
+                .violation(self.property.clone(), description.as_ref());
+        }
+        self
+    }
+
+    /// Checks that the value is present, i.e., set and not empty.
+    pub fn required(self) -> Self
+    where
+        T: Presence,
+    {
+        let is_present = self.value.is_present();
+        self.rule(is_present, "Required")
+    }
+
+    /// Checks the number of characters or elements, if the value is present.
+    pub fn length<R: RangeBounds<usize>>(self, range: R) -> Self
+    where
+        T: Length,
+    {
+        match self.value.length() {
+            Some(length) if !range.contains(&length) => {
+                let description = format!("Length must be {}", describe_bounds(&range));
+                self.rule(false, description)
+            }
+            _ => self,
+        }
+    }
+
+    /// Checks that the value is within `range`.
+    pub fn range<V, R>(self, range: R) -> Self
+    where
+        T: PartialOrd<V>,
+        V: PartialOrd<T> + fmt::Display,
+        R: RangeBounds<V>,
+    {
+        let is_valid = range.contains(self.value);
+        self.rule(is_valid, format!("Must be {}", describe_bounds(&range)))
+    }
+
+    /// Checks that the value equals one of the `allowed` values, e.g., the
+    /// names of an enumeration.
+    pub fn member_of<V>(self, allowed: &[V]) -> Self
+    where
+        T: PartialEq<V>,
+        V: fmt::Display,
+    {
+        let is_valid = allowed.iter().any(|allowed| self.value == allowed);
+        let allowed: Vec<String> = allowed.iter().map(ToString::to_string).collect();
+        self.rule(is_valid, format!("Must be one of: {}", allowed.join(", ")))
+    }
+
+    /// Checks that the value matches `pattern`, if the value is not empty.
+    #[cfg(feature = "with-regex")]
+    pub fn pattern(self, pattern: &regex::Regex) -> Self
+    where
+        T: AsRef<str>,
+    {
+        let value = self.value.as_ref();
+        let is_valid = value.is_empty() || pattern.is_match(value);
+        self.rule(is_valid, format!("Must match pattern {pattern}"))
+    }
+}
+
+fn describe_bounds<V: fmt::Display, R: RangeBounds<V>>(range: &R) -> String {
+    match (range.start_bound(), range.end_bound()) {
+        (Bound::Included(start), Bound::Included(end)) => format!("between {start} and {end}"),
+        (start, end) => {
+            let start = match start {
+                Bound::Included(start) => Some(format!("at least {start}")),
+                Bound::Excluded(start) => Some(format!("greater than {start}")),
+                Bound::Unbounded => None,
+            };
+            let end = match end {
+                Bound::Included(end) => Some(format!("at most {end}")),
+                Bound::Excluded(end) => Some(format!("less than {end}")),
+                Bound::Unbounded => None,
+            };
+            let bounds: Vec<String> = start.into_iter().chain(end).collect();
+            bounds.join(" and ")
+        }
+    }
+}
+
+/// A value that may be absent or empty.
+pub trait Presence {
+    fn is_present(&self) -> bool;
+}
+
+impl Presence for str {
+    fn is_present(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+impl Presence for String {
+    fn is_present(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+impl<T> Presence for [T] {
+    fn is_present(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+impl<T> Presence for Vec<T> {
+    fn is_present(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+impl<T> Presence for Option<T> {
+    fn is_present(&self) -> bool {
+        self.is_some()
+    }
+}
+
+/// A value with a length, counted in characters for strings.
+pub trait Length {
+    /// Returns the length, or `None` when the value is absent.
+    fn length(&self) -> Option<usize>;
+}
+
+impl Length for str {
+    fn length(&self) -> Option<usize> {
+        Some(self.chars().count())
+    }
+}
+
+impl Length for String {
+    fn length(&self) -> Option<usize> {
+        self.as_str().length()
+    }
+}
+
+impl<T> Length for [T] {
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T> Length for Vec<T> {
+    fn length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T: Length> Length for Option<T> {
+    fn length(&self) -> Option<usize> {
+        self.as_ref().and_then(Length::length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    struct Child {
+        name: String,
+        nicknames: Vec<String>,
+    }
+
+    struct Parent {
+        email: Option<String>,
+        phone: Option<String>,
+        kind: String,
+        children: Vec<Child>,
+        pets: BTreeMap<String, Child>,
+        spouse: Option<Box<Parent>>,
+    }
+
+    impl Validate for Child {
+        fn validate(&self, validator: &mut Validator) {
+            validator.field("name", &self.name).required().length(..=5);
+            for (index, nickname) in self.nicknames.iter().enumerate() {
+                validator
+                    .check(
+                        Property::ArrayMember {
+                            name: "nicknames".to_string(),
+                            index,
+                        },
+                        nickname,
+                    )
+                    .length(2..);
+            }
+        }
+    }
+
+    impl Validate for Parent {
+        fn validate(&self, validator: &mut Validator) {
+            validator
+                .one_of(
+                    "contact",
+                    &[
+                        ("email", self.email.is_some()),
+                        ("phone", self.phone.is_some()),
+                    ],
+                )
+                .field("email", &self.email)
+                .length(3..=64);
+            validator
+                .field("kind", &self.kind)
+                .member_of(&["PARENT", "GUARDIAN"]);
+            validator
+                .array("children", &self.children)
+                .map("pets", &self.pets)
+                .member("spouse", &self.spouse);
+        }
+    }
+
+    fn child(name: &str) -> Child {
+        Child {
+            name: name.to_string(),
+            nicknames: Vec::new(),
+        }
+    }
+
+    fn valid_parent() -> Parent {
+        Parent {
+            email: Some("kris@appbiotic.com".to_string()),
+            phone: None,
+            kind: "PARENT".to_string(),
+            children: vec![child("Ada")],
+            pets: BTreeMap::new(),
+            spouse: None,
+        }
+    }
+
+    #[test]
+    fn valid_request() {
+        assert!(Validator::validate(&valid_parent()).is_ok());
+    }
+
+    #[test]
+    fn collects_nested_violations() {
+        let parent = Parent {
+            email: Some("k".to_string()),
+            phone: Some("555-0100".to_string()),
+            kind: "UNCLE".to_string(),
+            children: vec![
+                child("Ada"),
+                Child {
+                    name: "Bartholomew".to_string(),
+                    nicknames: vec!["Bart".to_string(), "B".to_string()],
+                },
+            ],
+            pets: BTreeMap::from([("rex".to_string(), child(""))]),
+            spouse: Some(Box::new(Parent {
+                email: None,
+                ..valid_parent()
+            })),
+        };
+
+        let error = Validator::validate(&parent).expect_err("invalid request");
+        assert_eq!(error.code(), Code::InvalidArgument);
+        let violations: Vec<(String, String)> = match error.inner().details.as_deref() {
+            Some([ErrorDetails::BadRequest { field_violations }]) => field_violations
+                .iter()
+                .map(|violation| {
+                    (
+                        violation.field.to_string(),
+                        violation.description.clone().unwrap_or_default(),
+                    )
+                })
+                .collect(),
+            other => panic!("unexpected details: {other:?}"),
+        };
+        let expected = [
+            ("contact", "Exactly one of email, phone must be set"),
+            ("email", "Length must be between 3 and 64"),
+            ("kind", "Must be one of: PARENT, GUARDIAN"),
+            ("children[1].name", "Length must be at most 5"),
+            ("children[1].nicknames[1]", "Length must be at least 2"),
+            (r#"pets["rex"].name"#, "Required"),
+            ("spouse.contact", "Exactly one of email, phone must be set"),
+        ];
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(field, description)| (field.to_string(), description.to_string()))
+            .collect();
+        assert_eq!(violations, expected);
+    }
+
+    #[test]
+    fn range_descriptions() {
+        let mut validator = Validator::new();
+        validator.field("a", &5).range(1..=3);
+        validator.field("b", &5).range(6..);
+        validator.field("c", &5.5).range(..5.0);
+        validator.field("d", &5).range(..=10);
+        let descriptions: Vec<&str> = validator
+            .field_violations()
+            .iter()
+            .filter_map(|violation| violation.description.as_deref())
+            .collect();
+        assert_eq!(
+            descriptions,
+            [
+                "Must be between 1 and 3",
+                "Must be at least 6",
+                "Must be less than 5"
+            ]
+        );
+    }
+
+    #[cfg(feature = "with-regex")]
+    #[test]
+    fn pattern_rule() {
+        let pattern = regex::Regex::new("^[A-Z][A-Z0-9_]+$").expect("regex");
+        let mut validator = Validator::new();
+        validator.field("reason", "NAME_TOO_LONG").pattern(&pattern);
+        validator.field("domain", "").pattern(&pattern);
+        validator.field("code", "name").pattern(&pattern);
+        assert_eq!(validator.field_violations().len(), 1);
+        assert_eq!(validator.field_violations()[0].field.to_string(), "code");
+    }
+}
@@ -14,7 +14,7 @@ pub mod commands {
     }
 
     impl CliCmd {
-        pub fn execute(&self) -> anyhow::Result<()> {
+        pub fn execute(&self) -> appbiotic_code_error::Result<()> {
             match &self {
                 Self::GetGreeting(args) => {
                     println!("{}", crate::greeter::get_greeting(args.name.as_deref()));
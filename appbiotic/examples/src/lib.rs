@@ -18,7 +18,7 @@ pub mod commands {
     }
 
     impl CliCmd {
-        pub fn execute(&self) -> anyhow::Result<()> {
+        pub fn execute(&self) -> appbiotic_code_error::Result<()> {
             match self {
                 #[cfg(feature = "appbiotic-examples-greeter")]
                 Self::Greeter(cmd) => cmd.execute(),